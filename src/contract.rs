@@ -1,20 +1,36 @@
 use cosmwasm_std::{
-    coin, entry_point, from_json, to_json_binary, BankMsg, Binary, CosmosMsg, Decimal, Deps,
-    DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
+    coin, entry_point, from_json, to_json_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg,
+    Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Uint128, WasmMsg,
 };
 use cw20::Cw20ReceiveMsg;
+use std::collections::BTreeSet;
 
 use crate::error::ContractError;
 use crate::msg::{
-    CountResponse, ExecuteMsg, FeeResponse, InstantiateMsg, Offer, OffersResponse, QueryMsg,
-    RentalInfo, RentalResponse, SellRwa,
+    AssetFeesResponse, AssetResponse, Booking, BookingsResponse, BundleItem, CollectedFeesResponse,
+    CountResponse, DenomFeesResponse, DepositBundle, ExecuteMsg, ExpiredRentalsResponse, ExpiryKey,
+    FeeRecipientInfo, FeeRecipientMsg, FeeResponse, FeeScheduleResponse, InstallmentPlanResponse,
+    InstantiateMsg, LenderResponse, LoanResponse, Offer, OffersResponse, OpenLoan,
+    OwnershipResponse, PauseStatusResponse, PendingActionResponse, PendingActionsResponse,
+    PendingBundleResponse, PendingIncomeResponse, PoolResponse, ProposalInfo, QueryMsg,
+    QuoteResponse, ReceiveHookMsg, Redeem, RedemptionRequestResponse, RedemptionWindowResponse,
+    RentalInfo, RentalResponse, ReturnRental, RolesResponse, RoyaltyPayment, SellBatch, SellRwa,
+    SimulateRentResponse, SubletRwa, TraderVolumeResponse,
 };
 use crate::state::{
-    get_fund, increment_offerings, Offering, Rental, State, OFFERINGS, RENTALS, STATE,
+    get_fund, increment_loans, increment_offerings, increment_redemptions, increment_rentals,
+    maybe_addr, Asset, BundleAsset, DenomFees, FeeRates, FeeRecipient, FeeSchedule,
+    GovernanceAction, GovernanceConfig, Holder, InstallmentPlan, InstallmentTerms, LendingTerms,
+    ListingType, Loan, Offering, Operation, PauseState, PendingBundle, PendingChange, Proposal,
+    RedemptionWindow, RentPeriod, Rental, RentalTerms, Role, Royalty, State, Timelocked,
+    VolumeTier, ASSETS, ASSET_FEES, BOOKINGS, BUY_PAUSES, COLLECTED_FEES, DENOM_FEES, EXPIRIES,
+    FEE_SCHEDULE, GOVERNANCE, HOLDERS, INSTALLMENT_PLANS, LOANS, OFFERINGS, PAUSE, PAUSED_ASSETS,
+    PENDING_BUNDLES, POOLS, POOL_SHARES, PROPOSALS, REDEMPTIONS, REDEMPTION_REQUESTS, RENTALS,
+    ROLES, SETTLEMENT_PAUSES, STATE, TRADER_VOLUME,
 };
 use cw2::set_contract_version;
-use cw_storage_plus::Bound;
-use std::ops::{Mul, Sub};
+use cw_storage_plus::{Bound, Map};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:rwa-protocol";
@@ -23,6 +39,13 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 30;
 
+const SECONDS_PER_YEAR: u64 = 31_536_000;
+const MAX_ROYALTY_RATE: Decimal = Decimal::percent(10);
+/// Shortest notice sellers get before a fee increase applies.
+const MIN_FEE_TIMELOCK: u64 = 86_400;
+/// Furthest ahead a reservation may start.
+const MAX_RESERVATION_LEAD: u64 = SECONDS_PER_YEAR;
+
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
@@ -32,13 +55,43 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    // a sale at the maximum fee must still be able to pay the maximum royalty
+    if msg.max_fee + MAX_ROYALTY_RATE > Decimal::one()
+        || msg.fee > msg.max_fee
+        || msg.fee_timelock < MIN_FEE_TIMELOCK
+    {
+        return Err(ContractError::InvalidFee {});
+    }
+
     let state = State {
         num_offerings: 0,
-        fee: msg.fee,
-        owner: info.sender,
+        num_rentals: 0,
+        num_loans: 0,
+        num_redemptions: 0,
+        owner: Some(info.sender),
+        pending_owner: None,
     };
     STATE.save(deps.storage, &state)?;
 
+    let schedule = FeeSchedule {
+        default: Timelocked::new(FeeRates {
+            buy: msg.fee,
+            rent: msg.fee,
+        }),
+        max_fee: msg.max_fee,
+        fee_timelock: msg.fee_timelock,
+        recipients: vec![],
+        referrer_share: Decimal::zero(),
+        keeper_bounty: None,
+    };
+    FEE_SCHEDULE.save(deps.storage, &schedule)?;
+
+    let governance = GovernanceConfig {
+        delay: msg.governance_delay,
+        num_proposals: 0,
+    };
+    GOVERNANCE.save(deps.storage, &governance)?;
+
     Ok(Response::default())
 }
 
@@ -51,62 +104,310 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Buy { offering_id } => execute_buy(deps, info, offering_id),
-        ExecuteMsg::WithdrawRwa { offering_id } => execute_withdraw(deps, info, offering_id),
-        ExecuteMsg::ReceiveRwa(msg) => execute_receive_rwa(deps, info, msg),
+        ExecuteMsg::Buy {
+            offering_id,
+            referrer,
+            max_price,
+            expected_fee,
+        } => execute_buy(
+            deps,
+            env,
+            info,
+            offering_id,
+            referrer,
+            BuyMode::Outright {
+                max_price,
+                expected_fee,
+            },
+        ),
+        ExecuteMsg::BatchBuy {
+            offering_ids,
+            max_total,
+        } => execute_batch_buy(deps, env, info, offering_ids, max_total),
+        ExecuteMsg::BatchWithdraw { offering_ids } => {
+            execute_batch_withdraw(deps, env, info, offering_ids)
+        }
+        ExecuteMsg::CreateBundle {
+            assets,
+            list_price,
+            allowed_buyers,
+        } => execute_create_bundle(deps, info, assets, list_price, allowed_buyers),
+        ExecuteMsg::CancelBundle { offering_id } => execute_cancel_bundle(deps, info, offering_id),
+        ExecuteMsg::BuyInstallments {
+            offering_id,
+            referrer,
+            max_total,
+        } => execute_buy(
+            deps,
+            env,
+            info,
+            offering_id,
+            referrer,
+            BuyMode::Installments { max_total },
+        ),
+        ExecuteMsg::PayInstallment { offering_id } => {
+            execute_pay_installment(deps, env, info, offering_id)
+        }
+        ExecuteMsg::ReclaimInstallments { offering_id } => {
+            execute_reclaim_installments(deps, env, info, offering_id)
+        }
+        ExecuteMsg::SetInstallmentTerms { offering_id, terms } => {
+            execute_set_installment_terms(deps, info, offering_id, terms)
+        }
+        ExecuteMsg::WithdrawRwa { offering_id } => execute_withdraw(deps, env, info, offering_id),
+        ExecuteMsg::ReceiveRwa(msg) => execute_receive_rwa(deps, env, info, msg),
         ExecuteMsg::WithdrawFees { amount, denom } => {
             execute_withdraw_fees(deps, info, amount, denom)
         }
-        ExecuteMsg::ChangeFee { fee } => execute_change_fee(deps, info, fee),
+        ExecuteMsg::ChangeFee { fee } => execute_change_fee(deps, env, info, fee),
         ExecuteMsg::RentRwa {
             offering_id,
             duration,
-        } => execute_rent_rwa(deps, env, info, offering_id, duration),
+            referrer,
+            max_total,
+        } => execute_rent_rwa(
+            deps,
+            env,
+            info,
+            offering_id,
+            duration,
+            referrer,
+            RentMode::Prepaid { max_total },
+        ),
+        ExecuteMsg::StreamRent {
+            offering_id,
+            duration,
+            referrer,
+            max_total,
+        } => execute_rent_rwa(
+            deps,
+            env,
+            info,
+            offering_id,
+            duration,
+            referrer,
+            RentMode::Streaming { max_total },
+        ),
+        ExecuteMsg::ReserveRental {
+            offering_id,
+            start_time,
+            duration,
+            max_total,
+        } => execute_rent_rwa(
+            deps,
+            env,
+            info,
+            offering_id,
+            duration,
+            None,
+            RentMode::Reserved {
+                start_time,
+                max_total,
+            },
+        ),
+        ExecuteMsg::ActivateRental { rental_id } => {
+            execute_activate_rental(deps, env, info, rental_id)
+        }
+        ExecuteMsg::TopUpRent { rental_id } => execute_top_up_rent(deps, env, info, rental_id),
+        ExecuteMsg::ClaimRent { rental_id } => execute_claim_rent(deps, env, info, rental_id),
         ExecuteMsg::EndRental { rental_id } => execute_end_rental(deps, env, info, rental_id),
         ExecuteMsg::Clawback { rental_id } => execute_clawback(deps, env, info, rental_id),
+        ExecuteMsg::RegisterAsset {
+            contract,
+            issuer,
+            income_denom,
+        } => execute_register_asset(deps, info, contract, issuer, income_denom),
+        ExecuteMsg::SetHolderShares {
+            contract,
+            holder,
+            shares,
+        } => execute_set_holder_shares(deps, info, contract, holder, shares),
+        ExecuteMsg::DepositIncome { contract } => execute_deposit_income(deps, info, contract),
+        ExecuteMsg::ClaimIncome { contract } => execute_claim_income(deps, info, contract),
+        ExecuteMsg::SetRoyalty {
+            contract,
+            recipient,
+            rate,
+        } => execute_set_royalty(deps, info, contract, recipient, rate),
+        ExecuteMsg::OpenRedemptionWindow {
+            contract,
+            price,
+            cap,
+            closes_at,
+        } => execute_open_redemption_window(deps, env, info, contract, price, cap, closes_at),
+        ExecuteMsg::CloseRedemptionWindow { window_id } => {
+            execute_close_redemption_window(deps, env, window_id)
+        }
+        ExecuteMsg::ClaimRedemption { window_id } => {
+            execute_claim_redemption(deps, env, info, window_id)
+        }
+        ExecuteMsg::SetAssetValuation { contract, price } => {
+            execute_set_asset_valuation(deps, info, contract, price)
+        }
+        ExecuteMsg::SetLendingTerms { contract, terms } => {
+            execute_set_lending_terms(deps, info, contract, terms)
+        }
+        ExecuteMsg::DepositLiquidity {} => execute_deposit_liquidity(deps, info),
+        ExecuteMsg::WithdrawLiquidity { denom, shares } => {
+            execute_withdraw_liquidity(deps, info, denom, shares)
+        }
+        ExecuteMsg::Borrow { loan_id, amount } => execute_borrow(deps, env, info, loan_id, amount),
+        ExecuteMsg::Repay { loan_id } => execute_repay(deps, env, info, loan_id),
+        ExecuteMsg::Liquidate { loan_id } => execute_liquidate(deps, env, loan_id),
+        ExecuteMsg::SetDefaultFees { rates } => execute_set_default_fees(deps, env, info, rates),
+        ExecuteMsg::SetAssetFees { contract, rates } => {
+            execute_set_asset_fees(deps, env, info, contract, rates)
+        }
+        ExecuteMsg::SetDenomFees {
+            denom,
+            rates,
+            min_fee,
+            tiers,
+        } => execute_set_denom_fees(deps, env, info, denom, rates, min_fee, tiers),
+        ExecuteMsg::SetFeeRecipients {
+            recipients,
+            referrer_share,
+        } => execute_set_fee_recipients(deps, info, recipients, referrer_share),
+        ExecuteMsg::SetKeeperBounty { bounty } => execute_set_keeper_bounty(deps, info, bounty),
+        ExecuteMsg::ProcessExpiredRentals { start_after, limit } => {
+            execute_process_expired_rentals(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, info, owner),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => execute_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => execute_revoke_role(deps, info, role, address),
+        ExecuteMsg::Pause { operations } => execute_pause(deps, env, info, operations),
+        ExecuteMsg::Unpause { operations } => execute_unpause(deps, env, info, operations),
+        ExecuteMsg::PauseAsset { contract } => execute_pause_asset(deps, env, info, contract, true),
+        ExecuteMsg::UnpauseAsset { contract } => {
+            execute_pause_asset(deps, env, info, contract, false)
+        }
+        ExecuteMsg::ExtendRental {
+            rental_id,
+            additional_duration,
+        } => execute_extend_rental(deps, env, info, rental_id, additional_duration),
+        ExecuteMsg::SetRenewals {
+            offering_id,
+            enabled,
+        } => execute_set_renewals(deps, info, offering_id, enabled),
+        ExecuteMsg::SetSubletPolicy {
+            offering_id,
+            allow_sublet,
+            lessor_cut,
+        } => execute_set_sublet_policy(deps, info, offering_id, allow_sublet, lessor_cut),
+        ExecuteMsg::SetRentToOwn {
+            offering_id,
+            equity_rate,
+        } => execute_set_rent_to_own(deps, info, offering_id, equity_rate),
+        ExecuteMsg::CompletePurchase { rental_id } => {
+            execute_complete_purchase(deps, env, rental_id)
+        }
+        ExecuteMsg::SetLateFees {
+            offering_id,
+            grace_period,
+            late_fee_rate,
+            security_deposit,
+        } => execute_set_late_fees(
+            deps,
+            info,
+            offering_id,
+            grace_period,
+            late_fee_rate,
+            security_deposit,
+        ),
+        ExecuteMsg::SetEarlyTerminationPenalty {
+            offering_id,
+            penalty,
+        } => execute_set_early_termination_penalty(deps, info, offering_id, penalty),
+        ExecuteMsg::ProposeAction { action } => execute_propose_action(deps, env, info, action),
+        ExecuteMsg::CancelAction { id } => execute_cancel_action(deps, info, id),
+        ExecuteMsg::ExecuteAction { id } => execute_execute_action(deps, env, info, id),
     }
 }
 
+/// How a purchase is paid for.
+pub enum BuyMode {
+    /// The full list price up front, within the buyer's limits.
+    Outright {
+        max_price: Option<Uint128>,
+        expected_fee: Option<Uint128>,
+    },
+    /// A down payment now, the rest on the offering's installment terms,
+    /// for a price of no more than `max_total`.
+    Installments { max_total: Option<Uint128> },
+}
+
 pub fn execute_buy(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     offering_id: String,
+    referrer: Option<String>,
+    mode: BuyMode,
 ) -> Result<Response, ContractError> {
     // check if offering exists
     let off = OFFERINGS.load(deps.storage, &offering_id)?;
-
-    if off.seller.eq(&info.sender) {
-        return Err(ContractError::InvalidBuyer {});
-    }
+    assert_buyable(deps.storage, &offering_id, &off, &info.sender)?;
 
     // check for enough coins
-    let off_fund = get_fund(info.funds.clone(), off.list_price.denom)?;
+    let off_fund = get_fund(info.funds.clone(), off.list_price.denom.clone())?;
+    let referrer = maybe_addr(deps.api, referrer)?;
+    let (max_price, expected_fee) = match mode {
+        BuyMode::Outright {
+            max_price,
+            expected_fee,
+        } => (max_price, expected_fee),
+        BuyMode::Installments { max_total } => {
+            if max_total.is_some_and(|max| off.list_price.amount > max) {
+                return Err(ContractError::MaxTotalExceeded {});
+            }
+            return start_installments(
+                deps,
+                env,
+                info.sender,
+                offering_id,
+                off,
+                off_fund,
+                referrer,
+            );
+        }
+    };
+    if max_price.is_some_and(|max| off.list_price.amount > max) {
+        return Err(ContractError::MaxPriceExceeded {});
+    }
     if off_fund.amount < off.list_price.amount {
         return Err(ContractError::InsufficientFunds {});
     }
-
-    let state = STATE.load(deps.storage)?;
-    let net_amount = Decimal::one().sub(state.fee).mul(off_fund.amount);
-    // create transfer msg
-    let transfer_msg: CosmosMsg = BankMsg::Send {
-        to_address: off.seller.clone().into(),
-        amount: vec![coin(net_amount.u128(), off_fund.denom.clone())],
+    if let Some(fee) = expected_fee {
+        let now = env.block.time.seconds();
+        let quote = quote_sale(
+            deps.storage,
+            now,
+            &off,
+            off.list_price.amount,
+            Some(&info.sender),
+        )?;
+        if quote.protocol_fee > fee {
+            return Err(ContractError::FeeExceeded {});
+        }
     }
-    .into();
-
-    // create transfer cw721 msg
-    let transfer_rwa_msg = WasmMsg::Execute {
-        contract_addr: off.contract.clone().into(),
-        msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.clone().into(),
-            amount: off.amount,
-        })?,
-        funds: vec![],
-    };
 
-    OFFERINGS.remove(deps.storage, &offering_id);
+    let (quote, mut messages) = purchase(
+        deps.storage,
+        env.block.time.seconds(),
+        &offering_id,
+        &off,
+        &info.sender,
+        referrer.as_ref(),
+    )?;
+    messages.extend(refund_excess(
+        &info.sender,
+        &off_fund,
+        off.list_price.amount,
+    ));
 
-    let price_string = format!("{}{}", off_fund.amount, off_fund.denom);
+    let price_string = format!("{}{}", off.list_price.amount, off.list_price.denom);
     let res = Response::new()
         .add_attribute("action", "buy_rwa")
         .add_attribute("buyer", info.sender)
@@ -114,429 +415,7351 @@ pub fn execute_buy(
         .add_attribute("paid_price", price_string)
         .add_attribute("amount", off.amount)
         .add_attribute("rwa_contract", off.contract)
-        .add_messages(vec![
-            transfer_msg,
-            cosmwasm_std::CosmosMsg::Wasm(transfer_rwa_msg),
-        ]);
+        .add_attribute("protocol_fee", quote.protocol_fee)
+        .add_attribute("royalty", quote.royalty)
+        .add_attribute("seller_amount", quote.seller_amount)
+        .add_messages(messages);
     Ok(res)
 }
 
-pub fn execute_withdraw(
+/// Buys every offering in one go, failing the whole batch if any can't be bought.
+pub fn execute_batch_buy(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    offering_id: String,
+    offering_ids: Vec<String>,
+    max_total: Coin,
 ) -> Result<Response, ContractError> {
-    let off = OFFERINGS.load(deps.storage, &offering_id)?;
-    if off.seller.ne(&info.sender) {
-        return Err(ContractError::Unauthorized {});
+    if offering_ids.is_empty() {
+        return Err(ContractError::InvalidBatch {});
     }
 
-    let transfer_rwa_msg = WasmMsg::Execute {
-        contract_addr: off.contract.into(),
-        msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-            recipient: off.seller.into(),
-            amount: off.amount,
-        })?,
-        funds: vec![],
-    };
+    let mut seen = BTreeSet::new();
+    let mut offerings = vec![];
+    let mut total = Uint128::zero();
+    for offering_id in offering_ids {
+        // every offering is loaded before any is bought, so a repeat would sell twice
+        if !seen.insert(offering_id.clone()) {
+            return Err(ContractError::InvalidBatch {});
+        }
+        let off = OFFERINGS.load(deps.storage, &offering_id)?;
+        assert_buyable(deps.storage, &offering_id, &off, &info.sender)?;
+        if off.list_price.denom != max_total.denom {
+            return Err(ContractError::InvalidBatch {});
+        }
+        total = total
+            .checked_add(off.list_price.amount)
+            .map_err(|_| ContractError::InvalidBatch {})?;
+        offerings.push((offering_id, off));
+    }
+    if total > max_total.amount {
+        return Err(ContractError::MaxTotalExceeded {});
+    }
+    let off_fund = get_fund(info.funds.clone(), max_total.denom.clone())?;
+    if off_fund.amount < total {
+        return Err(ContractError::InsufficientFunds {});
+    }
 
-    OFFERINGS.remove(deps.storage, &offering_id);
+    let now = env.block.time.seconds();
+    let mut messages = vec![];
+    let mut protocol_fee = Uint128::zero();
+    for (offering_id, off) in &offerings {
+        let (quote, msgs) = purchase(deps.storage, now, offering_id, off, &info.sender, None)?;
+        protocol_fee += quote.protocol_fee;
+        messages.extend(msgs);
+    }
+    messages.extend(refund_excess(&info.sender, &off_fund, total));
 
-    let res = Response::new()
-        .add_attribute("action", "withdraw_rwa")
-        .add_attribute("seller", info.sender)
-        .add_message(transfer_rwa_msg);
-    Ok(res)
+    let offering_ids: Vec<_> = offerings.into_iter().map(|(id, _)| id).collect();
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "batch_buy")
+        .add_attribute("buyer", info.sender)
+        .add_attribute("offering_ids", offering_ids.join(","))
+        .add_attribute(
+            "paid_price",
+            coin(total.u128(), max_total.denom).to_string(),
+        )
+        .add_attribute("protocol_fee", protocol_fee))
 }
 
-pub fn execute_receive_rwa(
-    deps: DepsMut,
-    info: MessageInfo,
-    wrapper: Cw20ReceiveMsg,
-) -> Result<Response, ContractError> {
-    let msg: SellRwa = from_json(&wrapper.msg)?;
-    let id = increment_offerings(deps.storage)?.to_string();
+/// Checks that `buyer` may buy the offering outright right now.
+fn assert_buyable(
+    store: &dyn Storage,
+    offering_id: &str,
+    off: &Offering,
+    buyer: &Addr,
+) -> Result<(), ContractError> {
+    assert_not_paused(store, Operation::Buy, &off.contract)?;
+    for asset in &off.bundle {
+        assert_not_paused(store, Operation::Buy, &asset.contract)?;
+    }
 
-    // save Offering
-    let off = Offering {
-        contract: info.sender.clone(),
-        amount: wrapper.amount,
-        seller: deps.api.addr_validate(&wrapper.sender)?,
-        list_price: msg.list_price.clone(),
-    };
-    OFFERINGS.save(deps.storage, &id, &off)?;
+    if off.seller.eq(buyer) {
+        return Err(ContractError::InvalidBuyer {});
+    }
+    if !off.listing_type.for_sale() {
+        return Err(ContractError::NotForSale {});
+    }
+    if !off.allows(buyer) {
+        return Err(ContractError::PrivateListing {});
+    }
+    assert_not_booked(store, offering_id)
+}
 
-    let price_string = format!("{}{}", msg.list_price.amount, msg.list_price.denom);
-    let res = Response::new()
-        .add_attribute("action", "sell_rwa")
-        .add_attribute("offering_id", id)
-        .add_attribute("rwa_contract", info.sender)
-        .add_attribute("seller", off.seller)
-        .add_attribute("list_price", price_string)
-        .add_attribute("amount", off.amount);
-    Ok(res)
+/// Settles the offering at its list price and hands the RWA to the buyer.
+fn purchase(
+    store: &mut dyn Storage,
+    now: u64,
+    offering_id: &str,
+    off: &Offering,
+    buyer: &Addr,
+    referrer: Option<&Addr>,
+) -> Result<(SaleQuote, Vec<CosmosMsg>), ContractError> {
+    let (quote, mut messages) = settle_sale(store, now, off, buyer, &off.list_price, referrer)?;
+    messages.extend(transfer_offering(off, buyer)?);
+    OFFERINGS.remove(store, offering_id);
+
+    Ok((quote, messages))
 }
 
-pub fn execute_withdraw_fees(
+/// Takes the down payment and holds the offering's RWA until it is paid off.
+fn start_installments(
     deps: DepsMut,
-    info: MessageInfo,
-    amount: Uint128,
-    denom: String,
+    env: Env,
+    buyer: Addr,
+    offering_id: String,
+    off: Offering,
+    payment: Coin,
+    referrer: Option<Addr>,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-
-    if state.owner.ne(&info.sender) {
-        return Err(ContractError::Unauthorized {});
+    let terms = off
+        .installment_terms
+        .clone()
+        .ok_or(ContractError::InstallmentsDisabled {})?;
+    let price = off.list_price.amount;
+    if payment.amount < terms.down_payment(price) {
+        return Err(ContractError::InsufficientFunds {});
     }
 
-    let transfer: CosmosMsg = BankMsg::Send {
-        to_address: state.owner.into(),
-        amount: vec![coin(amount.into(), denom)],
+    let now = env.block.time.seconds();
+    let paid = payment.amount.min(price);
+    let mut messages = refund_excess(&buyer, &payment, paid);
+    OFFERINGS.remove(deps.storage, &offering_id);
+    let plan = InstallmentPlan {
+        offering: off,
+        terms,
+        buyer,
+        referrer,
+        start_time: now,
+        paid,
+    };
+    if paid == price {
+        messages.extend(complete_installments(deps.storage, now, &plan)?);
+    } else {
+        INSTALLMENT_PLANS.save(deps.storage, &offering_id, &plan)?;
     }
-    .into();
 
-    Ok(Response::new().add_message(transfer))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "buy_installments")
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("buyer", plan.buyer)
+        .add_attribute("seller", plan.offering.seller)
+        .add_attribute("paid", paid))
 }
 
-pub fn execute_change_fee(
+pub fn execute_pay_installment(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    fee: Decimal,
+    offering_id: String,
 ) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.owner.ne(&info.sender) {
-            return Err(ContractError::Unauthorized {});
-        }
+    let mut plan = INSTALLMENT_PLANS.load(deps.storage, &offering_id)?;
+    if info.sender != plan.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_not_paused(deps.storage, Operation::Buy, &plan.offering.contract)?;
 
-        state.fee = fee;
-        Ok(state)
-    })?;
+    let price = &plan.offering.list_price;
+    let payment = get_fund(info.funds, price.denom.clone())?;
+    let applied = payment.amount.min(price.amount - plan.paid);
+    plan.paid += applied;
+    let remaining = price.amount - plan.paid;
 
-    let res = Response::new()
-        .add_attribute("action", "change_fee")
-        .add_attribute("fee", fee.to_string());
-    Ok(res)
+    let mut messages = refund_excess(&info.sender, &payment, applied);
+    if remaining.is_zero() {
+        messages.extend(complete_installments(
+            deps.storage,
+            env.block.time.seconds(),
+            &plan,
+        )?);
+        INSTALLMENT_PLANS.remove(deps.storage, &offering_id);
+    } else {
+        INSTALLMENT_PLANS.save(deps.storage, &offering_id, &plan)?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "pay_installment")
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("paid", applied)
+        .add_attribute("remaining", remaining))
 }
 
-pub fn execute_rent_rwa(
+/// Whether the plan is behind, with the time buying was paused taken off its schedule.
+fn installments_defaulted(
+    store: &dyn Storage,
+    plan: &InstallmentPlan,
+    now: u64,
+) -> StdResult<bool> {
+    let contract = &plan.offering.contract;
+    let paused = paused_seconds(store, &BUY_PAUSES, contract, plan.start_time, now)?;
+    Ok(plan.defaulted(now, paused))
+}
+
+pub fn execute_reclaim_installments(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     offering_id: String,
-    duration: u64,
 ) -> Result<Response, ContractError> {
-    let off = OFFERINGS.load(deps.storage, &offering_id)?;
-
-    if off.seller == info.sender {
-        return Err(ContractError::InvalidRenter {});
+    let plan = INSTALLMENT_PLANS.load(deps.storage, &offering_id)?;
+    let off = &plan.offering;
+    if info.sender != off.seller {
+        return Err(ContractError::Unauthorized {});
     }
-
-    let rental_price = off.list_price.amount * Uint128::from(duration);
-    let payment = get_fund(info.funds, off.list_price.denom.clone())?;
-    if payment.amount < rental_price {
-        return Err(ContractError::InsufficientFunds {});
+    assert_not_paused(deps.storage, Operation::Buy, &off.contract)?;
+    if !installments_defaulted(deps.storage, &plan, env.block.time.seconds())? {
+        return Err(ContractError::InstallmentsCurrent {});
     }
 
-    let state = STATE.load(deps.storage)?;
-    let fee_amount = rental_price * state.fee;
-    let seller_amount = rental_price - fee_amount;
-
-    let transfer_to_seller = BankMsg::Send {
-        to_address: off.seller.to_string(),
-        amount: vec![coin(seller_amount.u128(), off.list_price.denom.clone())],
-    };
-
-    let transfer_rwa = WasmMsg::Execute {
-        contract_addr: off.contract.to_string(),
-        msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.to_string(),
-            amount: off.amount,
-        })?,
-        funds: vec![],
-    };
-
-    let rental_id = increment_rentals(deps.storage)?.to_string();
-    let rental = Rental {
-        id: rental_id.clone(),
-        offering_id: offering_id.clone(),
-        renter: info.sender.clone(),
-        start_time: env.block.time.seconds(),
-        end_time: env.block.time.seconds() + duration,
-        amount: off.amount,
-    };
-    RENTALS.save(deps.storage, &rental_id, &rental)?;
+    let denom = &off.list_price.denom;
+    let kept = plan.paid * plan.terms.forfeit_rate;
+    let refund = plan.paid - kept;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (recipient, amount) in [(&off.seller, kept), (&plan.buyer, refund)] {
+        if !amount.is_zero() {
+            messages.push(
+                BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: vec![coin(amount.u128(), denom)],
+                }
+                .into(),
+            );
+        }
+    }
+    messages.extend(transfer_offering(off, &off.seller)?);
+    INSTALLMENT_PLANS.remove(deps.storage, &offering_id);
 
     Ok(Response::new()
-        .add_message(transfer_to_seller)
-        .add_message(transfer_rwa)
-        .add_attribute("action", "rent_rwa")
-        .add_attribute("rental_id", rental_id)
+        .add_messages(messages)
+        .add_attribute("action", "reclaim_installments")
         .add_attribute("offering_id", offering_id)
-        .add_attribute("renter", info.sender)
-        .add_attribute("duration", duration.to_string()))
+        .add_attribute("buyer", plan.buyer)
+        .add_attribute("kept", kept)
+        .add_attribute("refund", refund))
 }
 
-pub fn execute_end_rental(
+/// Settles a fully paid plan like an outright sale and releases the RWA.
+fn complete_installments(
+    store: &mut dyn Storage,
+    now: u64,
+    plan: &InstallmentPlan,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let off = &plan.offering;
+    let (_, mut messages) = settle_sale(
+        store,
+        now,
+        off,
+        &plan.buyer,
+        &off.list_price,
+        plan.referrer.as_ref(),
+    )?;
+    messages.extend(transfer_offering(off, &plan.buyer)?);
+    Ok(messages)
+}
+
+/// Returns whatever of `payment` beyond `used` to the payer.
+fn refund_excess(payer: &Addr, payment: &Coin, used: Uint128) -> Vec<CosmosMsg> {
+    if payment.amount <= used {
+        return vec![];
+    }
+    vec![BankMsg::Send {
+        to_address: payer.to_string(),
+        amount: vec![coin((payment.amount - used).u128(), &payment.denom)],
+    }
+    .into()]
+}
+
+pub fn execute_set_installment_terms(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
-    rental_id: String,
+    offering_id: String,
+    terms: Option<InstallmentTerms>,
 ) -> Result<Response, ContractError> {
-    let rental = RENTALS.load(deps.storage, &rental_id)?;
-
-    if env.block.time.seconds() < rental.end_time {
-        return Err(ContractError::RentalNotExpired {});
+    let mut off = OFFERINGS.load(deps.storage, &offering_id)?;
+    if off.seller.ne(&info.sender) || off.loan_id.is_some() {
+        return Err(ContractError::Unauthorized {});
     }
 
-    if info.sender != rental.renter {
-        return Err(ContractError::Unauthorized {});
+    if let Some(terms) = &terms {
+        if !off.listing_type.for_sale() {
+            return Err(ContractError::NotForSale {});
+        }
+        if terms.installments == 0
+            || terms.interval == 0
+            || terms.down_payment > Decimal::one()
+            || terms.forfeit_rate > Decimal::one()
+        {
+            return Err(ContractError::InvalidInstallmentTerms {});
+        }
     }
+    off.installment_terms = terms;
+    OFFERINGS.save(deps.storage, &offering_id, &off)?;
 
-    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_installment_terms")
+        .add_attribute("offering_id", offering_id))
+}
 
-    let transfer_rwa = WasmMsg::Execute {
-        contract_addr: off.contract.to_string(),
-        msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-            recipient: off.seller.to_string(),
-            amount: rental.amount,
-        })?,
-        funds: vec![],
+/// Pays out `paid` for `off` to the seller, royalty recipient and fee recipients.
+fn settle_sale(
+    store: &mut dyn Storage,
+    now: u64,
+    off: &Offering,
+    buyer: &Addr,
+    paid: &Coin,
+    referrer: Option<&Addr>,
+) -> Result<(SaleQuote, Vec<CosmosMsg>), ContractError> {
+    let quote = quote_sale(store, now, off, paid.amount, Some(buyer))?;
+    record_volume(store, buyer, &paid.denom, paid.amount)?;
+    let proceeds = coin(quote.seller_amount.u128(), paid.denom.clone());
+    let mut messages: Vec<CosmosMsg> = match off.loan_id {
+        Some(loan_id) => close_liquidation(store, now, loan_id, proceeds)?,
+        // create transfer msg
+        None => vec![BankMsg::Send {
+            to_address: off.seller.clone().into(),
+            amount: vec![proceeds],
+        }
+        .into()],
     };
+    for (recipient, royalty) in &quote.royalties {
+        messages.push(
+            BankMsg::Send {
+                to_address: recipient.into(),
+                amount: vec![coin(royalty.u128(), paid.denom.clone())],
+            }
+            .into(),
+        );
+    }
+    messages.extend(fee_messages(
+        store,
+        coin(quote.protocol_fee.u128(), &paid.denom),
+        referrer,
+    )?);
 
-    RENTALS.remove(deps.storage, &rental_id);
+    Ok((quote, messages))
+}
 
-    Ok(Response::new()
-        .add_message(transfer_rwa)
-        .add_attribute("action", "end_rental")
-        .add_attribute("rental_id", rental_id)
-        .add_attribute("renter", rental.renter))
+/// Sells a rent-to-own offering to its renter once their equity covers the
+/// list price, settling the rest of the rental and cancelling reservations
+/// that haven't started. Waits while the lot is sublet, rented by someone else
+/// or paused for sale.
+fn complete_purchase(
+    store: &mut dyn Storage,
+    now: u64,
+    off: &Offering,
+    rental: &Rental,
+) -> Result<Option<Vec<CosmosMsg>>, ContractError> {
+    let price = &off.list_price;
+    if rental.equity_rate.is_zero()
+        || rental.equity < price.amount
+        || !rental.active
+        || rental.sublet_offering_id.is_some()
+        || assert_not_paused(store, Operation::Buy, &off.contract).is_err()
+    {
+        return Ok(None);
+    }
+    let mut messages =
+        match cancel_reservations(store, now, off, &rental.offering_id, Some(&rental.id)) {
+            Err(ContractError::OfferingBooked {}) => return Ok(None),
+            result => result?,
+        };
+
+    // a renter still holding the lot keeps it, a returned one gets it back
+    let (_, sale_messages) = settle_sale(store, now, off, &rental.renter, price, None)?;
+    messages.extend(sale_messages);
+    if rental.returned_at.is_some() {
+        messages.push(transfer_to_renter(off, &rental.renter)?);
+    }
+    let used = rental.accrued(now);
+    let covered = late_fee(store, &off.contract, rental, now)?.min(rental.deposit);
+    messages.extend(release_rent(store, off, used + covered - rental.claimed)?);
+    let refund = rental.escrow - used + rental.equity - price.amount + rental.deposit - covered;
+    if !refund.is_zero() {
+        messages.push(
+            BankMsg::Send {
+                to_address: rental.renter.to_string(),
+                amount: vec![coin(refund.u128(), &price.denom)],
+            }
+            .into(),
+        );
+    }
+    remove_rental(store, rental);
+    OFFERINGS.remove(store, &rental.offering_id);
+
+    Ok(Some(messages))
 }
 
-pub fn execute_clawback(
+pub fn execute_complete_purchase(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
     rental_id: String,
 ) -> Result<Response, ContractError> {
     let rental = RENTALS
         .may_load(deps.storage, &rental_id)?
         .ok_or(ContractError::RentalNotFound {})?;
     let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    let messages = complete_purchase(deps.storage, env.block.time.seconds(), &off, &rental)?
+        .ok_or(ContractError::PurchaseBlocked {})?;
 
-    if info.sender != off.seller {
-        return Err(ContractError::Unauthorized {});
-    }
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "complete_purchase")
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("renter", rental.renter))
+}
 
-    if env.block.time.seconds() < rental.end_time {
-        return Err(ContractError::RentalNotExpired {});
+/// Equity a settled rental forfeits to the seller: all of it after missed
+/// payments, none if it covered the price but the purchase never completed.
+fn forfeited_equity(off: &Offering, rental: &Rental) -> Uint128 {
+    if rental.equity >= off.list_price.amount {
+        return Uint128::zero();
     }
+    rental.equity
+}
 
-    let transfer_rwa = WasmMsg::Execute {
-        contract_addr: off.contract.to_string(),
-        msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-            recipient: off.seller.to_string(),
-            amount: rental.amount,
-        })?,
-        funds: vec![],
-    };
+pub fn execute_set_rent_to_own(
+    deps: DepsMut,
+    info: MessageInfo,
+    offering_id: String,
+    equity_rate: Decimal,
+) -> Result<Response, ContractError> {
+    let mut off = OFFERINGS.load(deps.storage, &offering_id)?;
+    if off.seller.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    RENTALS.remove(deps.storage, &rental_id);
+    // equity is paid out of rent, so both have to be in the same denom
+    if !equity_rate.is_zero()
+        && (equity_rate > Decimal::one()
+            || off.listing_type != ListingType::Both
+            || off.list_price.denom != off.rental_terms.rate.denom)
+    {
+        return Err(ContractError::InvalidRentToOwn {});
+    }
+    off.equity_rate = equity_rate;
+    OFFERINGS.save(deps.storage, &offering_id, &off)?;
 
     Ok(Response::new()
-        .add_message(transfer_rwa)
-        .add_attribute("action", "clawback")
-        .add_attribute("rental_id", rental_id)
-        .add_attribute("seller", off.seller))
+        .add_attribute("action", "set_rent_to_own")
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("equity_rate", equity_rate.to_string()))
 }
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetCount {} => to_json_binary(&query_count(deps)?),
-        QueryMsg::GetFee {} => to_json_binary(&query_fee(deps)?),
-        QueryMsg::AllOffers { start_after, limit } => {
-            to_json_binary(&query_all(deps, start_after, limit)?)
-        }
-        QueryMsg::GetRental { rental_id } => to_json_binary(&query_rental(deps, rental_id)?),
+pub fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offering_id: String,
+) -> Result<Response, ContractError> {
+    let transfer_rwa_msgs = withdraw_offering(
+        deps.storage,
+        env.block.time.seconds(),
+        &info.sender,
+        &offering_id,
+    )?;
+
+    let res = Response::new()
+        .add_attribute("action", "withdraw_rwa")
+        .add_attribute("seller", info.sender)
+        .add_messages(transfer_rwa_msgs);
+    Ok(res)
+}
+
+pub fn execute_batch_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offering_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let mut messages = vec![];
+    for offering_id in &offering_ids {
+        messages.extend(withdraw_offering(
+            deps.storage,
+            now,
+            &info.sender,
+            offering_id,
+        )?);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "batch_withdraw")
+        .add_attribute("seller", info.sender)
+        .add_attribute("offering_ids", offering_ids.join(","))
+        .add_messages(messages))
+}
+
+/// Removes the seller's offering and returns the transfer of its RWA back,
+/// cancelling and refunding reservations that haven't started.
+fn withdraw_offering(
+    store: &mut dyn Storage,
+    now: u64,
+    seller: &Addr,
+    offering_id: &str,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let off = OFFERINGS.load(store, offering_id)?;
+    // forced listings of loan collateral can't be pulled by the borrower
+    if off.seller.ne(seller) || off.loan_id.is_some() {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut messages = cancel_reservations(store, now, &off, offering_id, None)?;
+    if let Some(parent_id) = &off.parent_rental_id {
+        let mut parent = RENTALS.load(store, parent_id)?;
+        parent.sublet_offering_id = None;
+        RENTALS.save(store, parent_id, &parent)?;
+    }
+
+    OFFERINGS.remove(store, offering_id);
+
+    messages.extend(transfer_offering(&off, &off.seller)?);
+    Ok(messages)
+}
+
+/// Cancels and refunds the offering's reservations that haven't started, other
+/// than `keep`. Fails without cancelling any if another booking has started.
+fn cancel_reservations(
+    store: &mut dyn Storage,
+    now: u64,
+    off: &Offering,
+    offering_id: &str,
+    keep: Option<&str>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut rentals = vec![];
+    for item in BOOKINGS
+        .prefix(offering_id)
+        .range(store, None, None, Order::Ascending)
+    {
+        let (_, rental_id) = item?;
+        if keep != Some(rental_id.as_str()) {
+            rentals.push(RENTALS.load(store, &rental_id)?);
+        }
+    }
+    if rentals
+        .iter()
+        .any(|rental| rental.active || rental.start_time <= now)
+    {
+        return Err(ContractError::OfferingBooked {});
+    }
+
+    let mut messages = vec![];
+    for rental in rentals {
+        messages.extend(cancel_reservation(off, &rental));
+        remove_rental(store, &rental);
+    }
+    Ok(messages)
+}
+
+/// Transfers of all the offering's tokens, bundled ones included.
+fn transfer_offering(off: &Offering, recipient: &Addr) -> StdResult<Vec<CosmosMsg>> {
+    let main = (&off.contract, off.amount);
+    let bundled = off
+        .bundle
+        .iter()
+        .map(|asset| (&asset.contract, asset.amount));
+    std::iter::once(main)
+        .chain(bundled)
+        .map(|(contract, amount)| {
+            Ok(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            }
+            .into())
+        })
+        .collect()
+}
+
+pub fn execute_create_bundle(
+    deps: DepsMut,
+    info: MessageInfo,
+    assets: Vec<BundleItem>,
+    list_price: Coin,
+    allowed_buyers: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    if assets.is_empty() || assets.iter().any(|asset| asset.amount.is_zero()) {
+        return Err(ContractError::InvalidBundle {});
+    }
+    let assets = assets
+        .into_iter()
+        .map(|asset| {
+            let contract = deps.api.addr_validate(&asset.contract)?;
+            assert_not_paused(deps.storage, Operation::Listing, &contract)?;
+            Ok(BundleAsset {
+                contract,
+                amount: asset.amount,
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    let allowed_buyers = allowed_buyers
+        .unwrap_or_default()
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<StdResult<_>>()?;
+
+    let id = increment_offerings(deps.storage)?.to_string();
+    let bundle = PendingBundle {
+        seller: info.sender.clone(),
+        list_price: list_price.clone(),
+        deposited: vec![false; assets.len()],
+        assets,
+        allowed_buyers,
+    };
+    PENDING_BUNDLES.save(deps.storage, &id, &bundle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_bundle")
+        .add_attribute("offering_id", id)
+        .add_attribute("seller", info.sender)
+        .add_attribute("list_price", list_price.to_string()))
+}
+
+/// Takes in one of the bundle's assets, listing the bundle once all are in.
+pub fn execute_deposit_bundle(
+    deps: DepsMut,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+    msg: DepositBundle,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, Operation::Listing, &info.sender)?;
+
+    let mut bundle = PENDING_BUNDLES.load(deps.storage, &msg.offering_id)?;
+    if wrapper.sender != bundle.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let slot = bundle
+        .assets
+        .iter()
+        .zip(&bundle.deposited)
+        .position(|(asset, deposited)| {
+            !deposited && asset.contract == info.sender && asset.amount == wrapper.amount
+        })
+        .ok_or(ContractError::InvalidBundle {})?;
+    bundle.deposited[slot] = true;
+
+    let res = Response::new()
+        .add_attribute("action", "deposit_bundle")
+        .add_attribute("offering_id", &msg.offering_id)
+        .add_attribute("rwa_contract", info.sender)
+        .add_attribute("amount", wrapper.amount);
+    if bundle.deposited.contains(&false) {
+        PENDING_BUNDLES.save(deps.storage, &msg.offering_id, &bundle)?;
+        return Ok(res);
+    }
+
+    let mut assets = bundle.assets.into_iter();
+    let Some(main) = assets.next() else {
+        return Err(ContractError::InvalidBundle {});
+    };
+    let off = Offering {
+        contract: main.contract,
+        amount: main.amount,
+        seller: bundle.seller,
+        list_price: bundle.list_price.clone(),
+        listing_type: ListingType::Sale,
+        rental_terms: RentalTerms {
+            rate: bundle.list_price,
+            period: RentPeriod::Second,
+            min_duration: 1,
+            max_duration: None,
+        },
+        renewals_enabled: false,
+        early_termination_penalty: Decimal::zero(),
+        allow_sublet: false,
+        sublet_cut: Decimal::zero(),
+        parent_rental_id: None,
+        equity_rate: Decimal::zero(),
+        grace_period: 0,
+        late_fee_rate: Uint128::zero(),
+        security_deposit: Uint128::zero(),
+        loan_id: None,
+        installment_terms: None,
+        allowed_buyers: bundle.allowed_buyers,
+        bundle: assets.collect(),
+    };
+    OFFERINGS.save(deps.storage, &msg.offering_id, &off)?;
+    PENDING_BUNDLES.remove(deps.storage, &msg.offering_id);
+
+    Ok(res.add_attribute("listed", "true"))
+}
+
+pub fn execute_cancel_bundle(
+    deps: DepsMut,
+    info: MessageInfo,
+    offering_id: String,
+) -> Result<Response, ContractError> {
+    let bundle = PENDING_BUNDLES.load(deps.storage, &offering_id)?;
+    if bundle.seller != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (asset, deposited) in bundle.assets.iter().zip(&bundle.deposited) {
+        if *deposited {
+            messages.push(
+                WasmMsg::Execute {
+                    contract_addr: asset.contract.to_string(),
+                    msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                        recipient: bundle.seller.to_string(),
+                        amount: asset.amount,
+                    })?,
+                    funds: vec![],
+                }
+                .into(),
+            );
+        }
+    }
+    PENDING_BUNDLES.remove(deps.storage, &offering_id);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "cancel_bundle")
+        .add_attribute("offering_id", offering_id))
+}
+
+pub fn execute_receive_rwa(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    if wrapper.msg.is_empty() {
+        return Err(ContractError::NoData {});
+    }
+    match from_json(&wrapper.msg)? {
+        ReceiveHookMsg::Sell(msg) => execute_sell_rwa(deps, info, wrapper, msg),
+        ReceiveHookMsg::DepositBundle(msg) => execute_deposit_bundle(deps, info, wrapper, msg),
+        ReceiveHookMsg::SellBatch(msg) => execute_sell_batch(deps, info, wrapper, msg),
+        ReceiveHookMsg::Sublet(msg) => execute_sublet_rwa(deps, env, info, wrapper, msg),
+        ReceiveHookMsg::Redeem(msg) => execute_redeem(deps, env, info, wrapper, msg),
+        ReceiveHookMsg::OpenLoan(msg) => execute_open_loan(deps, env, info, wrapper, msg),
+        ReceiveHookMsg::ReturnRental(msg) => execute_return_rental(deps, env, info, wrapper, msg),
+    }
+}
+
+pub fn execute_sell_rwa(
+    deps: DepsMut,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+    msg: SellRwa,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, Operation::Listing, &info.sender)?;
+
+    let seller = deps.api.addr_validate(&wrapper.sender)?;
+    let (id, off) = list_offering(
+        deps.storage,
+        deps.api,
+        &info.sender,
+        &seller,
+        wrapper.amount,
+        msg,
+    )?;
+
+    let price_string = format!("{}{}", off.list_price.amount, off.list_price.denom);
+    let res = Response::new()
+        .add_attribute("action", "sell_rwa")
+        .add_attribute("offering_id", id)
+        .add_attribute("rwa_contract", info.sender)
+        .add_attribute("seller", off.seller)
+        .add_attribute("list_price", price_string)
+        .add_attribute("amount", off.amount);
+    Ok(res)
+}
+
+/// Splits the attached tokens into one offering per lot.
+pub fn execute_sell_batch(
+    deps: DepsMut,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+    msg: SellBatch,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, Operation::Listing, &info.sender)?;
+
+    let lotted = msg
+        .lots
+        .iter()
+        .try_fold(Uint128::zero(), |total, lot| total.checked_add(lot.amount))
+        .map_err(|_| ContractError::InvalidBatch {})?;
+    if msg.lots.is_empty() || lotted != wrapper.amount {
+        return Err(ContractError::InvalidBatch {});
+    }
+
+    let seller = deps.api.addr_validate(&wrapper.sender)?;
+    let mut offering_ids = vec![];
+    for lot in msg.lots {
+        if lot.amount.is_zero() {
+            return Err(ContractError::InvalidBatch {});
+        }
+        let (id, _) = list_offering(
+            deps.storage,
+            deps.api,
+            &info.sender,
+            &seller,
+            lot.amount,
+            lot.listing,
+        )?;
+        offering_ids.push(id);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "sell_batch")
+        .add_attribute("offering_ids", offering_ids.join(","))
+        .add_attribute("rwa_contract", info.sender)
+        .add_attribute("seller", seller)
+        .add_attribute("amount", wrapper.amount))
+}
+
+fn list_offering(
+    store: &mut dyn Storage,
+    api: &dyn Api,
+    contract: &Addr,
+    seller: &Addr,
+    amount: Uint128,
+    msg: SellRwa,
+) -> Result<(String, Offering), ContractError> {
+    let rental_terms = msg.rental_terms.unwrap_or(RentalTerms {
+        rate: msg.list_price.clone(),
+        period: RentPeriod::Second,
+        min_duration: 1,
+        max_duration: None,
+    });
+    if rental_terms.min_duration == 0
+        || rental_terms
+            .max_duration
+            .is_some_and(|max| max < rental_terms.min_duration)
+    {
+        return Err(ContractError::InvalidDuration {});
+    }
+    let allowed_buyers = msg
+        .allowed_buyers
+        .unwrap_or_default()
+        .iter()
+        .map(|addr| api.addr_validate(addr))
+        .collect::<StdResult<_>>()?;
+    let id = increment_offerings(store)?.to_string();
+
+    // save Offering
+    let off = Offering {
+        contract: contract.clone(),
+        amount,
+        seller: seller.clone(),
+        list_price: msg.list_price,
+        listing_type: msg.listing_type.unwrap_or(ListingType::Both),
+        rental_terms,
+        renewals_enabled: true,
+        early_termination_penalty: Decimal::zero(),
+        allow_sublet: false,
+        sublet_cut: Decimal::zero(),
+        parent_rental_id: None,
+        equity_rate: Decimal::zero(),
+        grace_period: 0,
+        late_fee_rate: Uint128::zero(),
+        security_deposit: Uint128::zero(),
+        loan_id: None,
+        installment_terms: None,
+        allowed_buyers,
+        bundle: vec![],
+    };
+    OFFERINGS.save(store, &id, &off)?;
+
+    Ok((id, off))
+}
+
+/// Lists the rest of an active rental as a rent-only offering of its own.
+pub fn execute_sublet_rwa(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+    msg: SubletRwa,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, Operation::Listing, &info.sender)?;
+
+    let mut rental = RENTALS
+        .may_load(deps.storage, &msg.rental_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    let parent = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    if wrapper.sender != rental.renter {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !parent.allow_sublet || rental.sublet_offering_id.is_some() {
+        return Err(ContractError::SubletNotAllowed {});
+    }
+    if !rental.active || env.block.time.seconds() >= rental.end_time {
+        return Err(ContractError::RentalNotFound {});
+    }
+
+    // the whole rented lot has to come back, priced in the parent's denom
+    let terms = msg.rental_terms;
+    if info.sender != parent.contract
+        || wrapper.amount != rental.amount
+        || terms.rate.denom != parent.rental_terms.rate.denom
+        || terms.min_duration == 0
+    {
+        return Err(ContractError::InvalidSubletTerms {});
+    }
+
+    let id = increment_offerings(deps.storage)?.to_string();
+    let off = Offering {
+        contract: parent.contract,
+        amount: wrapper.amount,
+        seller: rental.renter.clone(),
+        list_price: terms.rate.clone(),
+        listing_type: ListingType::Rent,
+        rental_terms: terms,
+        renewals_enabled: true,
+        early_termination_penalty: Decimal::zero(),
+        allow_sublet: false,
+        sublet_cut: Decimal::zero(),
+        parent_rental_id: Some(rental.id.clone()),
+        equity_rate: Decimal::zero(),
+        grace_period: 0,
+        late_fee_rate: Uint128::zero(),
+        security_deposit: Uint128::zero(),
+        loan_id: None,
+        installment_terms: None,
+        allowed_buyers: vec![],
+        bundle: vec![],
+    };
+    OFFERINGS.save(deps.storage, &id, &off)?;
+
+    rental.sublet_offering_id = Some(id.clone());
+    RENTALS.save(deps.storage, &rental.id, &rental)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sublet_rwa")
+        .add_attribute("offering_id", id)
+        .add_attribute("rental_id", rental.id)
+        .add_attribute("seller", off.seller))
+}
+
+pub fn execute_set_sublet_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    offering_id: String,
+    allow_sublet: bool,
+    lessor_cut: Decimal,
+) -> Result<Response, ContractError> {
+    let mut off = OFFERINGS.load(deps.storage, &offering_id)?;
+    if off.seller.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if lessor_cut > Decimal::one() {
+        return Err(ContractError::InvalidSubletTerms {});
+    }
+    off.allow_sublet = allow_sublet;
+    off.sublet_cut = lessor_cut;
+    OFFERINGS.save(deps.storage, &offering_id, &off)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_sublet_policy")
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("allow_sublet", allow_sublet.to_string())
+        .add_attribute("lessor_cut", lessor_cut.to_string()))
+}
+
+pub fn execute_withdraw_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+    denom: String,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::Treasurer)?;
+
+    // everything else the contract holds belongs to sellers, renters and lenders
+    COLLECTED_FEES.update(deps.storage, &denom, |collected| {
+        collected
+            .unwrap_or_default()
+            .checked_sub(amount)
+            .map_err(|_| ContractError::InsufficientFees {})
+    })?;
+
+    let transfer: CosmosMsg = BankMsg::Send {
+        to_address: info.sender.into(),
+        amount: vec![coin(amount.into(), denom)],
+    }
+    .into();
+
+    Ok(Response::new().add_message(transfer))
+}
+
+pub fn execute_change_fee(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fee: Decimal,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::FeeManager)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    change_fee(deps, env, fee)
+}
+
+fn change_fee(deps: DepsMut, env: Env, fee: Decimal) -> Result<Response, ContractError> {
+    let rates = FeeRates {
+        buy: fee,
+        rent: fee,
+    };
+    let effective_at = set_default_rates(deps.storage, env.block.time.seconds(), rates)?;
+
+    let res = Response::new()
+        .add_attribute("action", "change_fee")
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("effective_at", effective_at.to_string());
+    Ok(res)
+}
+
+/// How a rental is paid for and when it starts. Each caps what the renter is
+/// charged, rent and security deposit together, at `max_total`.
+pub enum RentMode {
+    /// Starts now, fully paid.
+    Prepaid { max_total: Option<Uint128> },
+    /// Starts now, paid for as far as the attached funds go.
+    Streaming { max_total: Option<Uint128> },
+    /// Starts at `start_time`, fully paid.
+    Reserved {
+        start_time: u64,
+        max_total: Option<Uint128>,
+    },
+}
+
+impl RentMode {
+    fn max_total(&self) -> Option<Uint128> {
+        match self {
+            RentMode::Prepaid { max_total }
+            | RentMode::Streaming { max_total }
+            | RentMode::Reserved { max_total, .. } => *max_total,
+        }
+    }
+}
+
+pub fn execute_rent_rwa(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offering_id: String,
+    duration: u64,
+    referrer: Option<String>,
+    mode: RentMode,
+) -> Result<Response, ContractError> {
+    let off = OFFERINGS.load(deps.storage, &offering_id)?;
+    let terms = &off.rental_terms;
+
+    let now = env.block.time.seconds();
+    let start_time = match mode {
+        RentMode::Reserved { start_time, .. }
+            if start_time <= now || start_time - now > MAX_RESERVATION_LEAD =>
+        {
+            return Err(ContractError::InvalidStartTime {})
+        }
+        RentMode::Reserved { start_time, .. } => start_time,
+        _ => now,
+    };
+    assert_rentable(
+        deps.storage,
+        now,
+        &offering_id,
+        &off,
+        Some(&info.sender),
+        start_time,
+        duration,
+    )?;
+
+    // the security deposit comes on top of the rent
+    let payment = get_fund(info.funds, terms.rate.denom.clone())?;
+    let rent_paid = payment
+        .amount
+        .checked_sub(off.security_deposit)
+        .map_err(|_| ContractError::InsufficientFunds {})?;
+    let prepaid = match mode {
+        RentMode::Streaming { .. } => terms.seconds_covered(rent_paid, duration),
+        _ => duration,
+    };
+    let rental_price = terms.price(prepaid)?;
+    if prepaid == 0 || rent_paid < rental_price {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    // the protocol fee comes out of the rent, so this is everything the renter is charged
+    if mode
+        .max_total()
+        .is_some_and(|max| rental_price + off.security_deposit > max)
+    {
+        return Err(ContractError::MaxTotalExceeded {});
+    }
+
+    // a reservation keeps the RWA in the contract until it is activated
+    let active = start_time == now;
+    let referrer = maybe_addr(deps.api, referrer)?;
+    let equity = rental_price * off.equity_rate;
+    let (fee_amount, escrow, mut messages) = charge_rent(
+        deps.storage,
+        now,
+        &off,
+        &info.sender,
+        rental_price - equity,
+        referrer.as_ref(),
+        !active,
+    )?;
+    messages.extend(refund_excess(
+        &info.sender,
+        &payment,
+        rental_price + off.security_deposit,
+    ));
+
+    if active {
+        messages.push(transfer_to_renter(&off, &info.sender)?);
+    }
+
+    let rental_id = increment_rentals(deps.storage)?.to_string();
+    let rental = Rental {
+        id: rental_id.clone(),
+        offering_id: offering_id.clone(),
+        renter: info.sender.clone(),
+        start_time,
+        end_time: start_time + duration,
+        amount: off.amount,
+        escrow,
+        paid_until: start_time + prepaid,
+        claimed: Uint128::zero(),
+        active,
+        lessor: off.seller.clone(),
+        parent_rental_id: off.parent_rental_id.clone(),
+        sublet_offering_id: None,
+        equity,
+        equity_rate: off.equity_rate,
+        early_termination_penalty: off.early_termination_penalty,
+        grace_period: off.grace_period,
+        late_fee_rate: off.late_fee_rate,
+        deposit: off.security_deposit,
+        returned_at: None,
+        fee_paid: fee_amount,
+        fee_held: if active { Uint128::zero() } else { fee_amount },
+    };
+    RENTALS.save(deps.storage, &rental_id, &rental)?;
+    BOOKINGS.save(deps.storage, (&offering_id, start_time), &rental_id)?;
+    EXPIRIES.save(deps.storage, (rental.end_time, &rental_id), &Empty {})?;
+    let purchase = complete_purchase(deps.storage, now, &off, &rental)?;
+    let purchased = purchase.is_some();
+    messages.extend(purchase.unwrap_or_default());
+
+    let action = if active { "rent_rwa" } else { "reserve_rental" };
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", action)
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("renter", info.sender)
+        .add_attribute("start_time", start_time.to_string())
+        .add_attribute("duration", duration.to_string())
+        .add_attribute("paid_until", rental.paid_until.to_string())
+        .add_attribute("protocol_fee", fee_amount)
+        .add_attribute("purchased", purchased.to_string()))
+}
+
+/// Fails where `renter` couldn't rent the offering for `duration` seconds from
+/// `start_time`. Shared by renting and its simulation so both apply the same checks.
+fn assert_rentable(
+    store: &dyn Storage,
+    now: u64,
+    offering_id: &str,
+    off: &Offering,
+    renter: Option<&Addr>,
+    start_time: u64,
+    duration: u64,
+) -> Result<(), ContractError> {
+    assert_not_paused(store, Operation::Rent, &off.contract)?;
+    if renter == Some(&off.seller) {
+        return Err(ContractError::InvalidRenter {});
+    }
+    if !off.listing_type.for_rent() {
+        return Err(ContractError::NotForRent {});
+    }
+    if !off.visible_to(renter) {
+        return Err(ContractError::PrivateListing {});
+    }
+    off.rental_terms.validate_duration(duration)?;
+
+    let end_time = start_time + duration;
+    assert_window_free(store, offering_id, start_time, end_time)?;
+    assert_within_parent(store, off, end_time)?;
+    assert_no_lease(store, offering_id)?;
+    // a rental starting now needs the RWA back from the one before
+    if start_time == now {
+        assert_lot_returned(store, now, offering_id)?;
+    }
+    Ok(())
+}
+
+fn transfer_to_renter(off: &Offering, renter: &Addr) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: off.contract.to_string(),
+        msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+            recipient: renter.to_string(),
+            amount: off.amount,
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Fails if `[start, end)` overlaps a booking of the offering.
+fn assert_window_free(
+    store: &dyn Storage,
+    offering_id: &str,
+    start: u64,
+    end: u64,
+) -> Result<(), ContractError> {
+    // bookings never overlap, so only the latest one starting before `end` can reach past `start`
+    let last = BOOKINGS
+        .prefix(offering_id)
+        .range(store, None, Some(Bound::exclusive(end)), Order::Descending)
+        .next()
+        .transpose()?;
+    if let Some((_, rental_id)) = last {
+        if RENTALS.load(store, &rental_id)?.end_time > start {
+            return Err(ContractError::WindowBooked {});
+        }
+    }
+    Ok(())
+}
+
+/// A rent-to-own lease may end in a sale, so nothing else can be booked while
+/// it runs.
+fn assert_no_lease(store: &dyn Storage, offering_id: &str) -> Result<(), ContractError> {
+    for item in BOOKINGS
+        .prefix(offering_id)
+        .range(store, None, None, Order::Ascending)
+    {
+        let (_, rental_id) = item?;
+        if !RENTALS.load(store, &rental_id)?.equity_rate.is_zero() {
+            return Err(ContractError::WindowBooked {});
+        }
+    }
+    Ok(())
+}
+
+/// A sublet can't outlast the rental it sublets.
+fn assert_within_parent(
+    store: &dyn Storage,
+    off: &Offering,
+    end: u64,
+) -> Result<(), ContractError> {
+    if let Some(parent_id) = &off.parent_rental_id {
+        if RENTALS.load(store, parent_id)?.end_time < end {
+            return Err(ContractError::InvalidDuration {});
+        }
+    }
+    Ok(())
+}
+
+/// Whether the rented RWA is back in the contract, or never left it.
+fn in_custody(store: &dyn Storage, rental: &Rental) -> StdResult<bool> {
+    if !rental.active || rental.returned_at.is_some() {
+        return Ok(true);
+    }
+    // a sublet lot was sent back to be listed, and is in as long as no sub-renter holds it
+    let Some(sublet_id) = &rental.sublet_offering_id else {
+        return Ok(false);
+    };
+    for item in BOOKINGS
+        .prefix(sublet_id)
+        .range(store, None, None, Order::Ascending)
+    {
+        let (_, rental_id) = item?;
+        if !in_custody(store, &RENTALS.load(store, &rental_id)?)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Fails while an earlier renter of the offering still holds its RWA.
+fn assert_lot_returned(
+    store: &dyn Storage,
+    now: u64,
+    offering_id: &str,
+) -> Result<(), ContractError> {
+    let started = BOOKINGS.prefix(offering_id).range(
+        store,
+        None,
+        Some(Bound::inclusive(now)),
+        Order::Ascending,
+    );
+    for item in started {
+        let (_, rental_id) = item?;
+        if !in_custody(store, &RENTALS.load(store, &rental_id)?)? {
+            return Err(ContractError::RwaNotReturned {});
+        }
+    }
+    Ok(())
+}
+
+fn assert_not_booked(store: &dyn Storage, offering_id: &str) -> Result<(), ContractError> {
+    let booked = BOOKINGS
+        .prefix(offering_id)
+        .keys(store, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if booked {
+        return Err(ContractError::OfferingBooked {});
+    }
+    Ok(())
+}
+
+pub fn execute_activate_rental(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    rental_id: String,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, &rental_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    assert_not_paused(deps.storage, Operation::Rent, &off.contract)?;
+
+    if rental.active {
+        return Err(ContractError::RentalAlreadyActive {});
+    }
+    let now = env.block.time.seconds();
+    if now < rental.start_time {
+        return Err(ContractError::RentalNotStarted {});
+    }
+    assert_lot_returned(deps.storage, now, &rental.offering_id)?;
+
+    let fee_messages = release_held_fee(deps.storage, &off, &rental)?;
+    rental.active = true;
+    rental.fee_held = Uint128::zero();
+    RENTALS.save(deps.storage, &rental_id, &rental)?;
+
+    Ok(Response::new()
+        .add_message(transfer_to_renter(&off, &rental.renter)?)
+        .add_messages(fee_messages)
+        .add_attribute("action", "activate_rental")
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("renter", rental.renter))
+}
+
+pub fn execute_return_rental(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+    msg: ReturnRental,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, &msg.rental_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    if wrapper.sender != rental.renter {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the whole rented lot has to come back, and only once
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    if in_custody(deps.storage, &rental)?
+        || info.sender != off.contract
+        || wrapper.amount != rental.amount
+    {
+        return Err(ContractError::InvalidReturn {});
+    }
+    rental.returned_at = Some(env.block.time.seconds());
+    RENTALS.save(deps.storage, &rental.id, &rental)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "return_rental")
+        .add_attribute("rental_id", rental.id)
+        .add_attribute("renter", rental.renter))
+}
+
+pub fn execute_top_up_rent(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rental_id: String,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, &rental_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    if info.sender != rental.renter {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    assert_not_paused(deps.storage, Operation::Rent, &off.contract)?;
+
+    let terms = &off.rental_terms;
+    let payment = get_fund(info.funds, terms.rate.denom.clone())?;
+    let prepaid = terms.seconds_covered(payment.amount, rental.end_time - rental.paid_until);
+    if prepaid == 0 {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    let now = env.block.time.seconds();
+    let rental_price = terms.price(prepaid)?;
+    let equity = rental_price * rental.equity_rate;
+    let (fee_amount, escrow, mut messages) = charge_rent(
+        deps.storage,
+        now,
+        &off,
+        &info.sender,
+        rental_price - equity,
+        None,
+        !rental.active,
+    )?;
+    messages.extend(refund_excess(&info.sender, &payment, rental_price));
+
+    rental.paid_until += prepaid;
+    rental.escrow += escrow;
+    rental.equity += equity;
+    rental.fee_paid += fee_amount;
+    if !rental.active {
+        rental.fee_held += fee_amount;
+    }
+    RENTALS.save(deps.storage, &rental_id, &rental)?;
+    let purchase = complete_purchase(deps.storage, now, &off, &rental)?;
+    let purchased = purchase.is_some();
+    messages.extend(purchase.unwrap_or_default());
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "top_up_rent")
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("renter", info.sender)
+        .add_attribute("paid_until", rental.paid_until.to_string())
+        .add_attribute("protocol_fee", fee_amount)
+        .add_attribute("purchased", purchased.to_string()))
+}
+
+pub fn execute_claim_rent(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rental_id: String,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, &rental_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    assert_not_paused(deps.storage, Operation::RentalSettlement, &off.contract)?;
+
+    if info.sender != off.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (amount, messages) = claim_owed(deps.storage, env.block.time.seconds(), &off, &mut rental)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "claim_rent")
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("amount", amount))
+}
+
+/// What the seller can take from `rental` before it settles: the rent accrued
+/// so far and the late fee its deposit covers. Nothing is earned on a
+/// reservation the renter never got.
+fn claimable(store: &dyn Storage, now: u64, off: &Offering, rental: &Rental) -> StdResult<Uint128> {
+    if !rental.active {
+        return Ok(Uint128::zero());
+    }
+    let covered = late_fee(store, &off.contract, rental, now)?.min(rental.deposit);
+    Ok(rental.accrued(now) + covered - rental.claimed)
+}
+
+/// Pays what is `claimable` on the rental out to the seller.
+fn claim_owed(
+    store: &mut dyn Storage,
+    now: u64,
+    off: &Offering,
+    rental: &mut Rental,
+) -> Result<(Uint128, Vec<CosmosMsg>), ContractError> {
+    let amount = claimable(store, now, off, rental)?;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    rental.claimed += amount;
+    RENTALS.save(store, &rental.id, rental)?;
+
+    Ok((amount, release_rent(store, off, amount)?))
+}
+
+pub fn execute_extend_rental(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rental_id: String,
+    additional_duration: u64,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, &rental_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    if info.sender != rental.renter {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    assert_not_paused(deps.storage, Operation::Rent, &off.contract)?;
+    if !off.renewals_enabled {
+        return Err(ContractError::RenewalsDisabled {});
+    }
+
+    // charged at the offering's current rate, within its maximum duration
+    let terms = &off.rental_terms;
+    if let Some(max) = terms.max_duration {
+        if rental.end_time - rental.start_time + additional_duration > max {
+            return Err(ContractError::InvalidDuration {});
+        }
+    }
+    assert_window_free(
+        deps.storage,
+        &rental.offering_id,
+        rental.end_time,
+        rental.end_time + additional_duration,
+    )?;
+    assert_within_parent(deps.storage, &off, rental.end_time + additional_duration)?;
+    let rental_price = terms.price(additional_duration)?;
+    let payment = get_fund(info.funds, terms.rate.denom.clone())?;
+    if payment.amount < rental_price {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    let now = env.block.time.seconds();
+    let equity = rental_price * rental.equity_rate;
+    let (fee_amount, escrow, mut messages) = charge_rent(
+        deps.storage,
+        now,
+        &off,
+        &info.sender,
+        rental_price - equity,
+        None,
+        !rental.active,
+    )?;
+    messages.extend(refund_excess(&info.sender, &payment, rental_price));
+
+    EXPIRIES.remove(deps.storage, (rental.end_time, &rental_id));
+    rental.end_time += additional_duration;
+    rental.paid_until += additional_duration;
+    rental.escrow += escrow;
+    rental.equity += equity;
+    rental.fee_paid += fee_amount;
+    if !rental.active {
+        rental.fee_held += fee_amount;
+    }
+    RENTALS.save(deps.storage, &rental_id, &rental)?;
+    EXPIRIES.save(deps.storage, (rental.end_time, &rental_id), &Empty {})?;
+    let purchase = complete_purchase(deps.storage, now, &off, &rental)?;
+    let purchased = purchase.is_some();
+    messages.extend(purchase.unwrap_or_default());
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "extend_rental")
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("renter", info.sender)
+        .add_attribute("end_time", rental.end_time.to_string())
+        .add_attribute("protocol_fee", fee_amount)
+        .add_attribute("purchased", purchased.to_string()))
+}
+
+pub fn execute_set_late_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    offering_id: String,
+    grace_period: u64,
+    late_fee_rate: Uint128,
+    security_deposit: Uint128,
+) -> Result<Response, ContractError> {
+    let mut off = OFFERINGS.load(deps.storage, &offering_id)?;
+    if off.seller.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    off.grace_period = grace_period;
+    off.late_fee_rate = late_fee_rate;
+    off.security_deposit = security_deposit;
+    OFFERINGS.save(deps.storage, &offering_id, &off)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_late_fees")
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("grace_period", grace_period.to_string())
+        .add_attribute("late_fee_rate", late_fee_rate)
+        .add_attribute("security_deposit", security_deposit))
+}
+
+/// Late fee owed at `now` for keeping the RWA past the end and grace period.
+/// Stops once the RWA is returned and doesn't run while settlement is paused.
+fn late_fee(store: &dyn Storage, contract: &Addr, rental: &Rental, now: u64) -> StdResult<Uint128> {
+    let due = rental.end_time.saturating_add(rental.grace_period);
+    let kept_until = rental.returned_at.unwrap_or(now);
+    if kept_until <= due {
+        return Ok(Uint128::zero());
+    }
+    let overdue =
+        kept_until - due - paused_seconds(store, &SETTLEMENT_PAUSES, contract, due, kept_until)?;
+    Ok(rental.late_fee_rate * Uint128::from(overdue))
+}
+
+pub fn execute_set_early_termination_penalty(
+    deps: DepsMut,
+    info: MessageInfo,
+    offering_id: String,
+    penalty: Decimal,
+) -> Result<Response, ContractError> {
+    let mut off = OFFERINGS.load(deps.storage, &offering_id)?;
+    if off.seller.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if penalty > Decimal::one() {
+        return Err(ContractError::InvalidPenalty {});
+    }
+    off.early_termination_penalty = penalty;
+    OFFERINGS.save(deps.storage, &offering_id, &off)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_early_termination_penalty")
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("penalty", penalty.to_string()))
+}
+
+pub fn execute_set_renewals(
+    deps: DepsMut,
+    info: MessageInfo,
+    offering_id: String,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let mut off = OFFERINGS.load(deps.storage, &offering_id)?;
+    if off.seller.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    off.renewals_enabled = enabled;
+    OFFERINGS.save(deps.storage, &offering_id, &off)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_renewals")
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+/// Takes the protocol fee out of `rent` paid by `renter`, returning the fee, the
+/// seller's share to escrow and the fee payouts. A `held` fee stays in the
+/// contract, paid out by `release_held_fee`.
+fn charge_rent(
+    store: &mut dyn Storage,
+    now: u64,
+    off: &Offering,
+    renter: &Addr,
+    rent: Uint128,
+    referrer: Option<&Addr>,
+    held: bool,
+) -> Result<(Uint128, Uint128, Vec<CosmosMsg>), ContractError> {
+    let denom = &off.rental_terms.rate.denom;
+    let fee_amount = rent_fee(store, now, off, Some(renter), rent)?;
+    let seller_amount = rent - fee_amount;
+    record_volume(store, renter, denom, rent)?;
+    if held {
+        return Ok((fee_amount, seller_amount, vec![]));
+    }
+
+    let messages = fee_messages(store, coin(fee_amount.u128(), denom), referrer)?;
+    Ok((fee_amount, seller_amount, messages))
+}
+
+/// Pays out the protocol fee held on a reservation once it can no longer be
+/// cancelled.
+fn release_held_fee(
+    store: &mut dyn Storage,
+    off: &Offering,
+    rental: &Rental,
+) -> StdResult<Vec<CosmosMsg>> {
+    if rental.fee_held.is_zero() {
+        return Ok(vec![]);
+    }
+    let fee = coin(rental.fee_held.u128(), &off.rental_terms.rate.denom);
+    fee_messages(store, fee, None)
+}
+
+/// Refunds everything paid for a reservation that never started.
+fn cancel_reservation(off: &Offering, rental: &Rental) -> Vec<CosmosMsg> {
+    let refund = rental.escrow + rental.equity + rental.deposit + rental.fee_held;
+    refund_renter(rental, &off.rental_terms.rate.denom, refund)
+}
+
+/// Protocol fee on `rent` paid by `renter` for `off`.
+fn rent_fee(
+    store: &dyn Storage,
+    now: u64,
+    off: &Offering,
+    renter: Option<&Addr>,
+    rent: Uint128,
+) -> StdResult<Uint128> {
+    compute_fee(
+        store,
+        now,
+        FeeKind::Rent,
+        &off.contract,
+        renter,
+        &coin(rent.u128(), &off.rental_terms.rate.denom),
+    )
+}
+
+/// Pays escrowed rent out to the seller, or to the asset's registered holders
+/// when it produces income.
+fn release_rent(
+    store: &mut dyn Storage,
+    off: &Offering,
+    amount: Uint128,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let denom = &off.rental_terms.rate.denom;
+    if amount.is_zero() {
+        return Ok(vec![]);
+    }
+
+    // sublet rent goes to the sublessor, less the original lessor's cut
+    if let Some(parent_id) = &off.parent_rental_id {
+        let parent = RENTALS.load(store, parent_id)?;
+        let parent_off = OFFERINGS.load(store, &parent.offering_id)?;
+        let cut = amount * parent_off.sublet_cut;
+        let mut messages = release_rent(store, &parent_off, cut)?;
+        if cut < amount {
+            messages.push(
+                BankMsg::Send {
+                    to_address: off.seller.to_string(),
+                    amount: vec![coin((amount - cut).u128(), denom)],
+                }
+                .into(),
+            );
+        }
+        return Ok(messages);
+    }
+
+    match ASSETS.may_load(store, &off.contract)? {
+        Some(mut asset) if asset.income_denom == *denom && !asset.total_shares.is_zero() => {
+            distribute_income(&mut asset, amount)?;
+            ASSETS.save(store, &off.contract, &asset)?;
+            Ok(vec![])
+        }
+        _ => Ok(vec![BankMsg::Send {
+            to_address: off.seller.to_string(),
+            amount: vec![coin(amount.u128(), denom)],
+        }
+        .into()]),
+    }
+}
+
+pub fn execute_end_rental(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rental_id: String,
+) -> Result<Response, ContractError> {
+    let rental = RENTALS.load(deps.storage, &rental_id)?;
+
+    if info.sender != rental.renter {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    assert_not_paused(deps.storage, Operation::RentalSettlement, &off.contract)?;
+
+    // the renter can't walk away from their own sublessees; an unbooked sublet lot is
+    // already back in the contract
+    match &rental.sublet_offering_id {
+        Some(sublet_id) => {
+            assert_not_booked(deps.storage, sublet_id)?;
+            OFFERINGS.remove(deps.storage, sublet_id);
+        }
+        None if !in_custody(deps.storage, &rental)? => {
+            return Err(ContractError::RwaNotReturned {})
+        }
+        None => {}
+    }
+
+    // ending early refunds the unused time, less the penalty agreed at booking
+    let now = env.block.time.seconds();
+    let used = rental.accrued(now);
+    let unused = rental.escrow - used;
+    let penalty = if now < rental.end_time {
+        unused * rental.early_termination_penalty
+    } else {
+        unused
+    };
+    let owed = late_fee(deps.storage, &off.contract, &rental, now)?;
+    let denom = &off.rental_terms.rate.denom;
+    let paid = info
+        .funds
+        .iter()
+        .find(|c| c.denom == *denom)
+        .map(|c| c.amount)
+        .unwrap_or_default();
+    if rental.deposit + paid < owed {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    // anything paid over the late fee goes back
+    let forfeited = forfeited_equity(&off, &rental);
+    let seller_amount = used + penalty + forfeited + owed - rental.claimed;
+    let refund = unused - penalty + rental.equity - forfeited + (rental.deposit + paid - owed);
+
+    let mut messages = release_rent(deps.storage, &off, seller_amount)?;
+    messages.extend(release_held_fee(deps.storage, &off, &rental)?);
+    if !refund.is_zero() {
+        messages.push(
+            BankMsg::Send {
+                to_address: rental.renter.to_string(),
+                amount: vec![coin(refund.u128(), &off.rental_terms.rate.denom)],
+            }
+            .into(),
+        );
+    }
+
+    remove_rental(deps.storage, &rental);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "end_rental")
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("renter", rental.renter)
+        .add_attribute("refund", refund)
+        .add_attribute("late_fee", owed))
+}
+
+pub fn execute_clawback(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rental_id: String,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, &rental_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    assert_not_paused(deps.storage, Operation::RentalSettlement, &off.contract)?;
+
+    if info.sender != rental.lessor {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // a streamed rental can be reclaimed once its prepaid time runs out
+    let now = env.block.time.seconds();
+    if now < rental.paid_until.min(rental.end_time) {
+        return Err(ContractError::RentalNotExpired {});
+    }
+
+    // a renter keeping the RWA still pays: the lessor takes the rent and the late fee the
+    // deposit covers so far, and the rental settles once it is returned
+    let returned = in_custody(deps.storage, &rental)?;
+    let messages = if returned {
+        settle_expired(deps.storage, now, &off, &rental)?
+    } else {
+        claim_owed(deps.storage, now, &off, &mut rental)?.1
+    };
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "clawback")
+        .add_attribute("rental_id", rental_id)
+        .add_attribute("seller", off.seller)
+        .add_attribute("settled", returned.to_string()))
+}
+
+/// Releases the escrow, any forfeited equity and the late fee the deposit covers
+/// of a returned rental to the seller; the RWA stays listed under its offering.
+/// A reservation that never activated is refunded instead.
+fn settle_expired(
+    store: &mut dyn Storage,
+    now: u64,
+    off: &Offering,
+    rental: &Rental,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if !rental.active {
+        remove_rental(store, rental);
+        return Ok(cancel_reservation(off, rental));
+    }
+    let covered = late_fee(store, &off.contract, rental, now)?.min(rental.deposit);
+    let forfeited = forfeited_equity(off, rental);
+    let seller_amount = rental.escrow + forfeited + covered - rental.claimed;
+    let mut messages = release_rent(store, off, seller_amount)?;
+    messages.extend(release_held_fee(store, off, rental)?);
+    messages.extend(refund_renter(
+        rental,
+        &off.rental_terms.rate.denom,
+        rental.deposit - covered + rental.equity - forfeited,
+    ));
+    if let Some(sublet_id) = &rental.sublet_offering_id {
+        messages.extend(close_sublet(store, now, sublet_id)?);
+    }
+    remove_rental(store, rental);
+
+    Ok(messages)
+}
+
+pub fn execute_process_expired_rentals(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<ExpiryKey>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let now = env.block.time.seconds();
+
+    let keeper_bounty = FEE_SCHEDULE.load(deps.storage)?.keeper_bounty;
+
+    // the limit caps rentals looked at, so ones that can't settle yet don't run the sweep
+    // out of gas
+    let mut messages = vec![];
+    let mut settled = 0usize;
+    let mut earned = Uint128::zero();
+    let mut last = start_after.map(|key| (key.end_time, key.rental_id));
+    for _ in 0..limit {
+        let Some(key) = next_expired(deps.storage, now, last.as_ref())? else {
+            break;
+        };
+        // sublets are settled along with their parent
+        let rental = RENTALS.may_load(deps.storage, &key.1)?;
+        last = Some(key);
+        let Some(rental) = rental else {
+            continue;
+        };
+        let Some(off) = sweepable(deps.storage, &rental)? else {
+            continue;
+        };
+        messages.extend(settle_expired(deps.storage, now, &off, &rental)?);
+        settled += 1;
+
+        // each rental's bounty comes out of the fee it paid, not one refunded with it
+        if let Some(bounty) = &keeper_bounty {
+            if bounty.denom == off.rental_terms.rate.denom {
+                earned += bounty.amount.min(rental.fee_paid - rental.fee_held);
+            }
+        }
+    }
+
+    let mut bounty = None;
+    if let Some(denom) = keeper_bounty.map(|b| b.denom) {
+        let collected = COLLECTED_FEES
+            .may_load(deps.storage, &denom)?
+            .unwrap_or_default();
+        let amount = earned.min(collected);
+        if !amount.is_zero() {
+            COLLECTED_FEES.save(deps.storage, &denom, &(collected - amount))?;
+            bounty = Some(coin(amount.u128(), denom));
+        }
+    }
+    if let Some(bounty) = &bounty {
+        messages.push(
+            BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![bounty.clone()],
+            }
+            .into(),
+        );
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "process_expired_rentals")
+        .add_attribute("settled", settled.to_string())
+        .add_attribute("bounty", bounty.map(|b| b.to_string()).unwrap_or_default()))
+}
+
+/// The first rental in the expiry queue after `after` that ended by `now`.
+fn next_expired(
+    store: &dyn Storage,
+    now: u64,
+    after: Option<&(u64, String)>,
+) -> StdResult<Option<(u64, String)>> {
+    let min = after.map(|(end_time, id)| Bound::exclusive((*end_time, id.as_str())));
+    let next = EXPIRIES
+        .keys(store, min, None, Order::Ascending)
+        .next()
+        .transpose()?;
+    Ok(next.filter(|(end_time, _)| *end_time <= now))
+}
+
+/// The offering of an expired rental the sweep can settle now: its RWA is
+/// back and settlement isn't paused.
+fn sweepable(store: &dyn Storage, rental: &Rental) -> StdResult<Option<Offering>> {
+    let off = OFFERINGS.load(store, &rental.offering_id)?;
+    if assert_not_paused(store, Operation::RentalSettlement, &off.contract).is_err()
+        || !in_custody(store, rental)?
+    {
+        return Ok(None);
+    }
+    Ok(Some(off))
+}
+
+pub fn execute_set_keeper_bounty(
+    deps: DepsMut,
+    info: MessageInfo,
+    bounty: Option<Coin>,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::FeeManager)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    set_keeper_bounty(deps, bounty)
+}
+
+fn set_keeper_bounty(deps: DepsMut, bounty: Option<Coin>) -> Result<Response, ContractError> {
+    FEE_SCHEDULE.update(deps.storage, |mut schedule| -> StdResult<_> {
+        schedule.keeper_bounty = bounty.clone();
+        Ok(schedule)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_keeper_bounty")
+        .add_attribute("bounty", bounty.map(|b| b.to_string()).unwrap_or_default()))
+}
+
+/// Settles every rental of a sublet offering at `now`, refunding the unused
+/// rent, and delists it.
+fn close_sublet(
+    store: &mut dyn Storage,
+    now: u64,
+    sublet_id: &str,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let sublet = OFFERINGS.load(store, sublet_id)?;
+    let rental_ids = BOOKINGS
+        .prefix(sublet_id)
+        .range(store, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, id)| id))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut messages = vec![];
+    for rental_id in rental_ids {
+        let rental = RENTALS.load(store, &rental_id)?;
+        if !rental.active {
+            messages.extend(cancel_reservation(&sublet, &rental));
+            remove_rental(store, &rental);
+            continue;
+        }
+        let used = rental.accrued(now);
+        let covered = late_fee(store, &sublet.contract, &rental, now)?.min(rental.deposit);
+        messages.extend(release_rent(
+            store,
+            &sublet,
+            used + covered - rental.claimed,
+        )?);
+        messages.extend(release_held_fee(store, &sublet, &rental)?);
+        let refund = rental.escrow - used + rental.deposit - covered;
+        if !refund.is_zero() {
+            messages.push(
+                BankMsg::Send {
+                    to_address: rental.renter.to_string(),
+                    amount: vec![coin(refund.u128(), &sublet.rental_terms.rate.denom)],
+                }
+                .into(),
+            );
+        }
+        remove_rental(store, &rental);
+    }
+    OFFERINGS.remove(store, sublet_id);
+
+    Ok(messages)
+}
+
+/// Sends `refund` back to the renter.
+fn refund_renter(rental: &Rental, denom: &str, refund: Uint128) -> Vec<CosmosMsg> {
+    if refund.is_zero() {
+        return vec![];
+    }
+    vec![BankMsg::Send {
+        to_address: rental.renter.to_string(),
+        amount: vec![coin(refund.u128(), denom)],
+    }
+    .into()]
+}
+
+fn remove_rental(store: &mut dyn Storage, rental: &Rental) {
+    RENTALS.remove(store, &rental.id);
+    BOOKINGS.remove(store, (&rental.offering_id, rental.start_time));
+    EXPIRIES.remove(store, (rental.end_time, &rental.id));
+}
+
+pub fn execute_register_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+    issuer: String,
+    income_denom: String,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::AssetCurator)?;
+
+    let contract = deps.api.addr_validate(&contract)?;
+    if ASSETS.has(deps.storage, &contract) {
+        return Err(ContractError::AssetAlreadyRegistered {});
+    }
+
+    let asset = Asset {
+        issuer: deps.api.addr_validate(&issuer)?,
+        income_denom,
+        total_shares: Uint128::zero(),
+        income_per_share: Decimal::zero(),
+        royalty: None,
+        valuation: None,
+        lending: None,
+    };
+    ASSETS.save(deps.storage, &contract, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_asset")
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("issuer", asset.issuer)
+        .add_attribute("income_denom", asset.income_denom))
+}
+
+pub fn execute_set_holder_shares(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+    holder: String,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut asset = ASSETS
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::AssetNotFound {})?;
+    if asset.issuer.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let holder_addr = deps.api.addr_validate(&holder)?;
+    let mut holder = HOLDERS
+        .may_load(deps.storage, (&contract, &holder_addr))?
+        .unwrap_or_else(|| Holder {
+            shares: Uint128::zero(),
+            income_per_share: asset.income_per_share,
+            pending: Uint128::zero(),
+        });
+
+    // income accrued on the old share count is kept before re-weighting
+    settle_holder(&asset, &mut holder);
+    asset.total_shares = asset.total_shares - holder.shares + shares;
+    holder.shares = shares;
+
+    HOLDERS.save(deps.storage, (&contract, &holder_addr), &holder)?;
+    ASSETS.save(deps.storage, &contract, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_holder_shares")
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("holder", holder_addr)
+        .add_attribute("shares", shares)
+        .add_attribute("total_shares", asset.total_shares))
+}
+
+pub fn execute_deposit_income(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut asset = ASSETS
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::AssetNotFound {})?;
+
+    let payment = get_fund(info.funds, asset.income_denom.clone())?;
+    if payment.amount.is_zero() {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    distribute_income(&mut asset, payment.amount)?;
+    ASSETS.save(deps.storage, &contract, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit_income")
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("depositor", info.sender)
+        .add_attribute("income", format!("{}{}", payment.amount, payment.denom)))
+}
+
+pub fn execute_claim_income(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let asset = ASSETS
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::AssetNotFound {})?;
+    let mut holder = HOLDERS
+        .may_load(deps.storage, (&contract, &info.sender))?
+        .ok_or(ContractError::NothingToClaim {})?;
+
+    settle_holder(&asset, &mut holder);
+    let amount = holder.pending;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    holder.pending = Uint128::zero();
+    HOLDERS.save(deps.storage, (&contract, &info.sender), &holder)?;
+
+    let transfer: CosmosMsg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![coin(amount.u128(), asset.income_denom.clone())],
+    }
+    .into();
+
+    Ok(Response::new()
+        .add_message(transfer)
+        .add_attribute("action", "claim_income")
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("holder", info.sender)
+        .add_attribute("income", format!("{}{}", amount, asset.income_denom)))
+}
+
+pub fn execute_set_royalty(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+    recipient: String,
+    rate: Decimal,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut asset = ASSETS
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::AssetNotFound {})?;
+    if asset.issuer.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if rate > MAX_ROYALTY_RATE {
+        return Err(ContractError::InvalidRoyalty {});
+    }
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+    asset.royalty = if rate.is_zero() {
+        None
+    } else {
+        Some(Royalty {
+            recipient: recipient.clone(),
+            rate,
+        })
+    };
+    ASSETS.save(deps.storage, &contract, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_royalty")
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("recipient", recipient)
+        .add_attribute("rate", rate.to_string()))
+}
+
+pub fn execute_open_redemption_window(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    price: Coin,
+    cap: Uint128,
+    closes_at: u64,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    assert_not_paused(deps.storage, Operation::Redemption, &contract)?;
+    let asset = ASSETS
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::AssetNotFound {})?;
+    if asset.issuer.ne(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if price.amount.is_zero() || cap.is_zero() || closes_at <= env.block.time.seconds() {
+        return Err(ContractError::InvalidRedemptionWindow {});
+    }
+
+    let funding = price.amount.checked_mul(cap).map_err(StdError::from)?;
+    let deposit = get_fund(info.funds, price.denom.clone())?;
+    if deposit.amount < funding {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    let window_id = increment_redemptions(deps.storage)?;
+    let window = RedemptionWindow {
+        contract: contract.clone(),
+        issuer: info.sender.clone(),
+        price: price.clone(),
+        cap,
+        funding,
+        closes_at,
+        requested: Uint128::zero(),
+        claimed: Uint128::zero(),
+        released: Uint128::zero(),
+        closed: false,
+    };
+    REDEMPTIONS.save(deps.storage, window_id, &window)?;
+
+    Ok(Response::new()
+        .add_messages(refund_excess(&info.sender, &deposit, funding))
+        .add_attribute("action", "open_redemption_window")
+        .add_attribute("window_id", window_id.to_string())
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("price", price.to_string())
+        .add_attribute("cap", cap)
+        .add_attribute("closes_at", closes_at.to_string()))
+}
+
+/// Queues the attached RWA tokens for redemption in an open window.
+pub fn execute_redeem(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+    msg: Redeem,
+) -> Result<Response, ContractError> {
+    let mut window = REDEMPTIONS.load(deps.storage, msg.window_id)?;
+    if info.sender != window.contract {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_not_paused(deps.storage, Operation::Redemption, &window.contract)?;
+    if env.block.time.seconds() >= window.closes_at {
+        return Err(ContractError::RedemptionClosed {});
+    }
+
+    let holder = deps.api.addr_validate(&wrapper.sender)?;
+    window.requested += wrapper.amount;
+    REDEMPTIONS.save(deps.storage, msg.window_id, &window)?;
+    REDEMPTION_REQUESTS.update(
+        deps.storage,
+        (msg.window_id, &holder),
+        |tokens| -> StdResult<_> { Ok(tokens.unwrap_or_default() + wrapper.amount) },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "redeem")
+        .add_attribute("window_id", msg.window_id.to_string())
+        .add_attribute("holder", holder)
+        .add_attribute("amount", wrapper.amount))
+}
+
+pub fn execute_close_redemption_window(
+    deps: DepsMut,
+    env: Env,
+    window_id: u64,
+) -> Result<Response, ContractError> {
+    let mut window = REDEMPTIONS.load(deps.storage, window_id)?;
+    if env.block.time.seconds() < window.closes_at {
+        return Err(ContractError::RedemptionOpen {});
+    }
+    if window.closed {
+        return Err(ContractError::RedemptionClosed {});
+    }
+
+    // keeps back enough for the claims still due; the rounding dust follows the last one
+    let reserved = window.fill(window.requested - window.claimed) * window.price.amount;
+    let refund = window.funding - window.released - reserved;
+    window.released += refund;
+    window.closed = true;
+    REDEMPTIONS.save(deps.storage, window_id, &window)?;
+    let refund = coin(refund.u128(), &window.price.denom);
+
+    let mut res = Response::new()
+        .add_attribute("action", "close_redemption_window")
+        .add_attribute("window_id", window_id.to_string())
+        .add_attribute("redeemed", window.requested.min(window.cap))
+        .add_attribute("refund", refund.to_string());
+    if !refund.amount.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: window.issuer.to_string(),
+            amount: vec![refund],
+        });
+    }
+    Ok(res)
+}
+
+/// Pays the holder for their filled tokens, which go to the issuer, and
+/// returns the rest.
+pub fn execute_claim_redemption(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    window_id: u64,
+) -> Result<Response, ContractError> {
+    let mut window = REDEMPTIONS.load(deps.storage, window_id)?;
+    if env.block.time.seconds() < window.closes_at {
+        return Err(ContractError::RedemptionOpen {});
+    }
+    let tokens = REDEMPTION_REQUESTS
+        .may_load(deps.storage, (window_id, &info.sender))?
+        .ok_or(ContractError::NothingToClaim {})?;
+    REDEMPTION_REQUESTS.remove(deps.storage, (window_id, &info.sender));
+
+    let filled = window.fill(tokens);
+    let payout = filled * window.price.amount;
+    window.claimed += tokens;
+    window.released += payout;
+
+    // pro-rata fills round down, so the last claim on a closed window returns the dust
+    let mut dust = Uint128::zero();
+    if window.closed && window.claimed == window.requested {
+        dust = window.funding - window.released;
+        window.released = window.funding;
+    }
+    REDEMPTIONS.save(deps.storage, window_id, &window)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (recipient, amount) in [(&info.sender, payout), (&window.issuer, dust)] {
+        if !amount.is_zero() {
+            messages.push(
+                BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: vec![coin(amount.u128(), &window.price.denom)],
+                }
+                .into(),
+            );
+        }
+    }
+    for (recipient, amount) in [(&window.issuer, filled), (&info.sender, tokens - filled)] {
+        if !amount.is_zero() {
+            messages.push(
+                WasmMsg::Execute {
+                    contract_addr: window.contract.to_string(),
+                    msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                        recipient: recipient.to_string(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                }
+                .into(),
+            );
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "claim_redemption")
+        .add_attribute("window_id", window_id.to_string())
+        .add_attribute("holder", info.sender)
+        .add_attribute("filled", filled)
+        .add_attribute("payout", payout))
+}
+
+pub fn execute_set_asset_valuation(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+    price: Coin,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::AssetCurator)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    set_asset_valuation(deps, contract, price)
+}
+
+fn set_asset_valuation(
+    deps: DepsMut,
+    contract: String,
+    price: Coin,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut asset = ASSETS
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::AssetNotFound {})?;
+    asset.valuation = Some(price.clone());
+    ASSETS.save(deps.storage, &contract, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_asset_valuation")
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("price", price.to_string()))
+}
+
+pub fn execute_set_lending_terms(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+    terms: Option<LendingTerms>,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::AssetCurator)?;
+
+    if let Some(terms) = &terms {
+        if terms.max_ltv.is_zero()
+            || terms.max_ltv >= terms.liquidation_ltv
+            || terms.liquidation_ltv > Decimal::one()
+            || terms.liquidation_discount >= Decimal::one()
+        {
+            return Err(ContractError::InvalidLendingTerms {});
+        }
+    }
+
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut asset = ASSETS
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::AssetNotFound {})?;
+    asset.lending = terms;
+    ASSETS.save(deps.storage, &contract, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_lending_terms")
+        .add_attribute("rwa_contract", contract))
+}
+
+pub fn execute_deposit_liquidity(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let deposit = match info.funds.as_slice() {
+        [deposit] if !deposit.amount.is_zero() => deposit,
+        _ => return Err(ContractError::InsufficientFunds {}),
+    };
+
+    let denom = &deposit.denom;
+    let mut pool = POOLS.may_load(deps.storage, denom)?.unwrap_or_default();
+    let shares = if pool.total_shares.is_zero() || pool.value().is_zero() {
+        deposit.amount
+    } else {
+        deposit
+            .amount
+            .multiply_ratio(pool.total_shares, pool.value())
+    };
+    pool.liquidity += deposit.amount;
+    pool.total_shares += shares;
+    POOLS.save(deps.storage, denom, &pool)?;
+    POOL_SHARES.update(
+        deps.storage,
+        (denom, &info.sender),
+        |held| -> StdResult<_> { Ok(held.unwrap_or_default() + shares) },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit_liquidity")
+        .add_attribute("lender", info.sender)
+        .add_attribute("amount", deposit.to_string())
+        .add_attribute("shares", shares))
+}
+
+pub fn execute_withdraw_liquidity(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    let held = POOL_SHARES
+        .may_load(deps.storage, (&denom, &info.sender))?
+        .unwrap_or_default();
+    if shares.is_zero() || shares > held {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    let mut pool = POOLS.load(deps.storage, &denom)?;
+    let amount = shares.multiply_ratio(pool.value(), pool.total_shares);
+    if amount > pool.liquidity {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+    pool.liquidity -= amount;
+    pool.total_shares -= shares;
+    POOLS.save(deps.storage, &denom, &pool)?;
+    POOL_SHARES.save(deps.storage, (&denom, &info.sender), &(held - shares))?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![coin(amount.u128(), &denom)],
+        })
+        .add_attribute("action", "withdraw_liquidity")
+        .add_attribute("lender", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("shares", shares))
+}
+
+/// Terms and token price for borrowing `denom` against the asset.
+fn lending_terms<'a>(
+    asset: &'a Asset,
+    denom: &str,
+) -> Result<(&'a LendingTerms, &'a Coin), ContractError> {
+    match (&asset.lending, &asset.valuation) {
+        (Some(terms), Some(price)) if price.denom == denom => Ok((terms, price)),
+        _ => Err(ContractError::LendingDisabled {}),
+    }
+}
+
+fn collateral_value(asset: &Asset, loan: &Loan) -> Option<Uint128> {
+    match &asset.valuation {
+        Some(price) if price.denom == loan.denom => Some(price.amount * loan.collateral),
+        _ => None,
+    }
+}
+
+/// Interest on the loan's debt since it was last accrued.
+fn interest_due(loan: &Loan, now: u64) -> Uint128 {
+    (loan.debt * loan.terms.interest_rate)
+        .multiply_ratio(now.saturating_sub(loan.accrued_at), SECONDS_PER_YEAR)
+}
+
+/// Adds the interest due to the loan's debt and to the pool it owes.
+fn accrue_interest(store: &mut dyn Storage, loan: &mut Loan, now: u64) -> StdResult<()> {
+    let interest = interest_due(loan, now);
+    loan.accrued_at = now;
+    if !interest.is_zero() {
+        loan.debt += interest;
+        let mut pool = POOLS.load(store, &loan.denom)?;
+        pool.borrowed += interest;
+        POOLS.save(store, &loan.denom, &pool)?;
+    }
+    Ok(())
+}
+
+/// Holds the attached RWA tokens as collateral for a new loan.
+pub fn execute_open_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+    msg: OpenLoan,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, Operation::Lending, &info.sender)?;
+
+    let asset = ASSETS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::AssetNotFound {})?;
+    let (terms, _) = lending_terms(&asset, &msg.denom)?;
+
+    let loan_id = increment_loans(deps.storage)?;
+    let loan = Loan {
+        borrower: deps.api.addr_validate(&wrapper.sender)?,
+        contract: info.sender.clone(),
+        collateral: wrapper.amount,
+        denom: msg.denom,
+        debt: Uint128::zero(),
+        accrued_at: env.block.time.seconds(),
+        offering_id: None,
+        terms: terms.clone(),
+    };
+    LOANS.save(deps.storage, loan_id, &loan)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "open_loan")
+        .add_attribute("loan_id", loan_id.to_string())
+        .add_attribute("borrower", loan.borrower)
+        .add_attribute("rwa_contract", info.sender)
+        .add_attribute("collateral", loan.collateral))
+}
+
+pub fn execute_borrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    loan_id: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut loan = LOANS
+        .may_load(deps.storage, loan_id)?
+        .ok_or(ContractError::LoanNotFound {})?;
+    if info.sender != loan.borrower {
+        return Err(ContractError::Unauthorized {});
+    }
+    if loan.offering_id.is_some() {
+        return Err(ContractError::LoanLiquidated {});
+    }
+    assert_not_paused(deps.storage, Operation::Lending, &loan.contract)?;
+
+    let asset = ASSETS.load(deps.storage, &loan.contract)?;
+    accrue_interest(deps.storage, &mut loan, env.block.time.seconds())?;
+    // disabled lending stops new draws, existing loans keep their terms
+    lending_terms(&asset, &loan.denom)?;
+    let value = collateral_value(&asset, &loan).unwrap_or_default();
+    if loan.debt + amount > value * loan.terms.max_ltv {
+        return Err(ContractError::ExceedsLtv {});
+    }
+
+    let mut pool = POOLS
+        .may_load(deps.storage, &loan.denom)?
+        .unwrap_or_default();
+    if amount > pool.liquidity {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+    pool.liquidity -= amount;
+    pool.borrowed += amount;
+    POOLS.save(deps.storage, &loan.denom, &pool)?;
+    loan.debt += amount;
+    LOANS.save(deps.storage, loan_id, &loan)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: loan.borrower.to_string(),
+            amount: vec![coin(amount.u128(), &loan.denom)],
+        })
+        .add_attribute("action", "borrow")
+        .add_attribute("loan_id", loan_id.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("debt", loan.debt))
+}
+
+pub fn execute_repay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    loan_id: u64,
+) -> Result<Response, ContractError> {
+    let mut loan = LOANS
+        .may_load(deps.storage, loan_id)?
+        .ok_or(ContractError::LoanNotFound {})?;
+
+    accrue_interest(deps.storage, &mut loan, env.block.time.seconds())?;
+    let payment = get_fund(info.funds, loan.denom.clone())?;
+    let repaid = payment.amount.min(loan.debt);
+    // a liquidated loan can still be repaid in full, which pulls the forced listing
+    if loan.offering_id.is_some() && repaid < loan.debt {
+        return Err(ContractError::LoanLiquidated {});
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !repaid.is_zero() {
+        let mut pool = POOLS.load(deps.storage, &loan.denom)?;
+        pool.liquidity += repaid;
+        pool.borrowed -= repaid;
+        POOLS.save(deps.storage, &loan.denom, &pool)?;
+        loan.debt -= repaid;
+    }
+    if repaid < payment.amount {
+        messages.push(
+            BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![coin((payment.amount - repaid).u128(), &loan.denom)],
+            }
+            .into(),
+        );
+    }
+
+    // a cleared loan hands the collateral back
+    if loan.debt.is_zero() {
+        if let Some(offering_id) = &loan.offering_id {
+            OFFERINGS.remove(deps.storage, offering_id);
+        }
+        messages.push(
+            WasmMsg::Execute {
+                contract_addr: loan.contract.to_string(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: loan.borrower.to_string(),
+                    amount: loan.collateral,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        );
+        LOANS.remove(deps.storage, loan_id);
+    } else {
+        LOANS.save(deps.storage, loan_id, &loan)?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "repay")
+        .add_attribute("loan_id", loan_id.to_string())
+        .add_attribute("repaid", repaid)
+        .add_attribute("debt", loan.debt))
+}
+
+pub fn execute_liquidate(deps: DepsMut, env: Env, loan_id: u64) -> Result<Response, ContractError> {
+    let mut loan = LOANS
+        .may_load(deps.storage, loan_id)?
+        .ok_or(ContractError::LoanNotFound {})?;
+    if loan.offering_id.is_some() {
+        return Err(ContractError::LoanLiquidated {});
+    }
+    assert_not_paused(deps.storage, Operation::Lending, &loan.contract)?;
+
+    let asset = ASSETS.load(deps.storage, &loan.contract)?;
+    accrue_interest(deps.storage, &mut loan, env.block.time.seconds())?;
+    // an unpriced loan can't be judged, let alone listed
+    let value = collateral_value(&asset, &loan).ok_or(ContractError::NoValuation {})?;
+    if loan.debt <= value * loan.terms.liquidation_ltv {
+        return Err(ContractError::LoanHealthy {});
+    }
+
+    // listed below value so the collateral clears, but never for less than the pool is owed
+    let price = (value - value * loan.terms.liquidation_discount).max(loan.debt);
+    let list_price = coin(price.u128(), &loan.denom);
+    let offering_id = increment_offerings(deps.storage)?.to_string();
+    let off = Offering {
+        contract: loan.contract.clone(),
+        amount: loan.collateral,
+        seller: loan.borrower.clone(),
+        list_price: list_price.clone(),
+        listing_type: ListingType::Sale,
+        rental_terms: RentalTerms {
+            rate: list_price.clone(),
+            period: RentPeriod::Second,
+            min_duration: 1,
+            max_duration: None,
+        },
+        renewals_enabled: false,
+        early_termination_penalty: Decimal::zero(),
+        allow_sublet: false,
+        sublet_cut: Decimal::zero(),
+        parent_rental_id: None,
+        equity_rate: Decimal::zero(),
+        grace_period: 0,
+        late_fee_rate: Uint128::zero(),
+        security_deposit: Uint128::zero(),
+        loan_id: Some(loan_id),
+        installment_terms: None,
+        allowed_buyers: vec![],
+        bundle: vec![],
+    };
+    OFFERINGS.save(deps.storage, &offering_id, &off)?;
+    loan.offering_id = Some(offering_id.clone());
+    LOANS.save(deps.storage, loan_id, &loan)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "liquidate")
+        .add_attribute("loan_id", loan_id.to_string())
+        .add_attribute("offering_id", offering_id)
+        .add_attribute("debt", loan.debt)
+        .add_attribute("list_price", list_price.to_string()))
+}
+
+/// Repays a liquidated loan from its sale proceeds, writing off any shortfall
+/// and passing the surplus to the borrower.
+fn close_liquidation(
+    store: &mut dyn Storage,
+    now: u64,
+    loan_id: u64,
+    proceeds: Coin,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut loan = LOANS.load(store, loan_id)?;
+    accrue_interest(store, &mut loan, now)?;
+
+    let repaid = proceeds.amount.min(loan.debt);
+    let mut pool = POOLS.load(store, &loan.denom)?;
+    pool.liquidity += repaid;
+    pool.borrowed -= loan.debt;
+    POOLS.save(store, &loan.denom, &pool)?;
+    LOANS.remove(store, loan_id);
+
+    let surplus = proceeds.amount - repaid;
+    if surplus.is_zero() {
+        return Ok(vec![]);
+    }
+    Ok(vec![BankMsg::Send {
+        to_address: loan.borrower.to_string(),
+        amount: vec![coin(surplus.u128(), proceeds.denom)],
+    }
+    .into()])
+}
+
+pub struct SaleQuote {
+    pub protocol_fee: Uint128,
+    /// Sum of `royalties`.
+    pub royalty: Uint128,
+    pub royalties: Vec<(Addr, Uint128)>,
+    pub seller_amount: Uint128,
+}
+
+/// Splits `price` paid by `buyer` for `off` into protocol fee, issuer royalties and seller
+/// proceeds. Each asset of a bundle is charged on its share of the price.
+pub fn quote_sale(
+    store: &dyn Storage,
+    now: u64,
+    off: &Offering,
+    price: Uint128,
+    buyer: Option<&Addr>,
+) -> StdResult<SaleQuote> {
+    let denom = &off.list_price.denom;
+    let mut protocol_fee = Uint128::zero();
+    let mut royalties = vec![];
+    for (contract, part) in price_shares(store, off, price)? {
+        protocol_fee += compute_fee(
+            store,
+            now,
+            FeeKind::Buy,
+            &contract,
+            buyer,
+            &coin(part.u128(), denom),
+        )?;
+        if let Some(royalty) = ASSETS.may_load(store, &contract)?.and_then(|a| a.royalty) {
+            let amount = part * royalty.rate;
+            if !amount.is_zero() {
+                royalties.push((royalty.recipient, amount));
+            }
+        }
+    }
+
+    let royalty = royalties.iter().map(|(_, amount)| amount).sum();
+    let seller_amount = price.checked_sub(protocol_fee)?.checked_sub(royalty)?;
+    Ok(SaleQuote {
+        protocol_fee,
+        royalty,
+        royalties,
+        seller_amount,
+    })
+}
+
+/// Splits `price` across the assets of `off` pro rata by their valuation in the
+/// price denom, or evenly if any of them has none.
+fn price_shares(
+    store: &dyn Storage,
+    off: &Offering,
+    price: Uint128,
+) -> StdResult<Vec<(Addr, Uint128)>> {
+    if off.bundle.is_empty() {
+        return Ok(vec![(off.contract.clone(), price)]);
+    }
+    let mut lots = vec![(off.contract.clone(), off.amount)];
+    lots.extend(off.bundle.iter().map(|a| (a.contract.clone(), a.amount)));
+
+    let mut values = vec![];
+    for (contract, amount) in &lots {
+        let valuation = ASSETS
+            .may_load(store, contract)?
+            .and_then(|asset| asset.valuation)
+            .filter(|valuation| valuation.denom == off.list_price.denom);
+        match valuation {
+            Some(valuation) => values.push(valuation.amount.checked_mul(*amount)?),
+            None => break,
+        }
+    }
+    if values.len() < lots.len() || values.iter().all(Uint128::is_zero) {
+        values = vec![Uint128::one(); lots.len()];
+    }
+    let total = values
+        .iter()
+        .try_fold(Uint128::zero(), |total, value| total.checked_add(*value))?;
+
+    // the last asset takes the rounding so the shares add up to the price
+    let mut left = price;
+    let count = lots.len();
+    Ok(lots
+        .into_iter()
+        .zip(values)
+        .enumerate()
+        .map(|(i, ((contract, _), value))| {
+            let share = if i + 1 == count {
+                left
+            } else {
+                price.multiply_ratio(value, total)
+            };
+            left -= share;
+            (contract, share)
+        })
+        .collect())
+}
+
+pub fn execute_set_default_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rates: FeeRates,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::FeeManager)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    set_default_fees(deps, env, rates)
+}
+
+fn set_default_fees(deps: DepsMut, env: Env, rates: FeeRates) -> Result<Response, ContractError> {
+    let effective_at = set_default_rates(deps.storage, env.block.time.seconds(), rates.clone())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_default_fees")
+        .add_attribute("buy_fee", rates.buy.to_string())
+        .add_attribute("rent_fee", rates.rent.to_string())
+        .add_attribute("effective_at", effective_at.to_string()))
+}
+
+/// Schedules new default rates, returning when they take effect.
+fn set_default_rates(
+    store: &mut dyn Storage,
+    now: u64,
+    rates: FeeRates,
+) -> Result<u64, ContractError> {
+    let mut schedule = FEE_SCHEDULE.load(store)?;
+    validate_rates(&schedule, &rates)?;
+
+    let raises = raises_rates(schedule.default.active(now), &rates);
+    let effective_at = fee_change_eta(&schedule, now, raises);
+    schedule.default.set(rates, now, effective_at);
+    FEE_SCHEDULE.save(store, &schedule)?;
+
+    Ok(effective_at.unwrap_or(now))
+}
+
+pub fn execute_set_asset_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    rates: Option<FeeRates>,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::FeeManager)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    set_asset_fees(deps, env, contract, rates)
+}
+
+fn set_asset_fees(
+    deps: DepsMut,
+    env: Env,
+    contract: String,
+    rates: Option<FeeRates>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let schedule = FEE_SCHEDULE.load(deps.storage)?;
+    if let Some(rates) = &rates {
+        validate_rates(&schedule, rates)?;
+    }
+
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut fees = ASSET_FEES
+        .may_load(deps.storage, &contract)?
+        .unwrap_or_else(|| Timelocked::new(None));
+
+    // an asset without an override pays the default rates
+    let default = schedule.default.active(now);
+    let old = fees.active(now).as_ref().unwrap_or(default);
+    let raises = raises_rates(old, rates.as_ref().unwrap_or(default));
+    let effective_at = fee_change_eta(&schedule, now, raises);
+    fees.set(rates, now, effective_at);
+    ASSET_FEES.save(deps.storage, &contract, &fees)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_asset_fees")
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("effective_at", effective_at.unwrap_or(now).to_string()))
+}
+
+pub fn execute_set_denom_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    rates: Option<FeeRates>,
+    min_fee: Uint128,
+    tiers: Vec<VolumeTier>,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::FeeManager)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    set_denom_fees(deps, env, denom, rates, min_fee, tiers)
+}
+
+fn set_denom_fees(
+    deps: DepsMut,
+    env: Env,
+    denom: String,
+    rates: Option<FeeRates>,
+    min_fee: Uint128,
+    mut tiers: Vec<VolumeTier>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let schedule = FEE_SCHEDULE.load(deps.storage)?;
+    if let Some(rates) = &rates {
+        validate_rates(&schedule, rates)?;
+    }
+    if tiers.iter().any(|tier| tier.discount > Decimal::one()) {
+        return Err(ContractError::InvalidFee {});
+    }
+    tiers.sort_by_key(|tier| tier.min_volume);
+
+    let new = DenomFees {
+        rates,
+        min_fee,
+        tiers,
+    };
+    let mut fees = DENOM_FEES
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_else(|| Timelocked::new(DenomFees::default()));
+
+    // any change to the volume tiers is treated as a potential increase
+    let default = schedule.default.active(now);
+    let old = fees.active(now);
+    let raises = raises_rates(
+        old.rates.as_ref().unwrap_or(default),
+        new.rates.as_ref().unwrap_or(default),
+    ) || new.min_fee > old.min_fee
+        || new.tiers != old.tiers;
+    let effective_at = fee_change_eta(&schedule, now, raises);
+    fees.set(new, now, effective_at);
+    DENOM_FEES.save(deps.storage, &denom, &fees)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_denom_fees")
+        .add_attribute("denom", denom)
+        .add_attribute("min_fee", min_fee)
+        .add_attribute("effective_at", effective_at.unwrap_or(now).to_string()))
+}
+
+fn validate_rates(schedule: &FeeSchedule, rates: &FeeRates) -> Result<(), ContractError> {
+    if rates.buy > schedule.max_fee || rates.rent > schedule.max_fee {
+        return Err(ContractError::InvalidFee {});
+    }
+    Ok(())
+}
+
+fn raises_rates(old: &FeeRates, new: &FeeRates) -> bool {
+    new.buy > old.buy || new.rent > old.rent
+}
+
+/// Fee increases wait out the fee timelock so sellers can react; decreases apply at once.
+fn fee_change_eta(schedule: &FeeSchedule, now: u64, raises: bool) -> Option<u64> {
+    raises.then(|| now + schedule.fee_timelock)
+}
+
+pub fn execute_set_fee_recipients(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<FeeRecipientMsg>,
+    referrer_share: Decimal,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::FeeManager)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    set_fee_recipients(deps, recipients, referrer_share)
+}
+
+fn set_fee_recipients(
+    deps: DepsMut,
+    recipients: Vec<FeeRecipientMsg>,
+    referrer_share: Decimal,
+) -> Result<Response, ContractError> {
+    let total = recipients
+        .iter()
+        .try_fold(referrer_share, |acc, r| acc.checked_add(r.share))
+        .map_err(|_| ContractError::InvalidFeeSplit {})?;
+    if total > Decimal::one() {
+        return Err(ContractError::InvalidFeeSplit {});
+    }
+
+    let recipients = recipients
+        .into_iter()
+        .map(|r| {
+            Ok(FeeRecipient {
+                address: deps.api.addr_validate(&r.address)?,
+                share: r.share,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    FEE_SCHEDULE.update(deps.storage, |mut schedule| -> StdResult<_> {
+        schedule.recipients = recipients;
+        schedule.referrer_share = referrer_share;
+        Ok(schedule)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_recipients")
+        .add_attribute("referrer_share", referrer_share.to_string()))
+}
+
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.as_ref(), &info)?;
+
+    let pending_owner = deps.api.addr_validate(&owner)?;
+    STATE.update(deps.storage, |mut state| -> StdResult<_> {
+        state.pending_owner = Some(pending_owner.clone());
+        Ok(state)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_owner")
+        .add_attribute("pending_owner", pending_owner))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        let pending_owner = state
+            .pending_owner
+            .take()
+            .ok_or(ContractError::NoPendingOwner {})?;
+        if pending_owner.ne(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        state.owner = Some(pending_owner);
+        Ok(state)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.as_ref(), &info)?;
+
+    STATE.update(deps.storage, |mut state| -> StdResult<_> {
+        state.owner = None;
+        state.pending_owner = None;
+        Ok(state)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "renounce_ownership")
+        .add_attribute("owner", info.sender))
+}
+
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.as_ref(), &info)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    grant_role(deps, role, address)
+}
+
+fn grant_role(deps: DepsMut, role: Role, address: String) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.as_ref(), &info)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    revoke_role(deps, role, address)
+}
+
+fn revoke_role(deps: DepsMut, role: Role, address: String) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.remove(deps.storage, (role.as_str(), &address));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn execute_pause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operations: Option<Vec<Operation>>,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::Pauser)?;
+
+    let mut pause = PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    let event = match operations {
+        Some(operations) => {
+            let event = pause_event("pause", &operations);
+            for op in operations {
+                if !pause.operations.contains(&op) {
+                    pause.operations.push(op);
+                }
+            }
+            event
+        }
+        None => {
+            pause.all = true;
+            Event::new("pause").add_attribute("scope", "all")
+        }
+    };
+    PAUSE.save(deps.storage, &pause)?;
+    record_pauses(deps.storage, "", env.block.time.seconds(), &pause)?;
+
+    Ok(Response::new()
+        .add_event(event.add_attribute("pauser", info.sender.clone()))
+        .add_attribute("action", "pause")
+        .add_attribute("pauser", info.sender))
+}
+
+pub fn execute_unpause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operations: Option<Vec<Operation>>,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::Pauser)?;
+    assert_no_governance_delay(deps.storage)?;
+
+    unpause(deps, env, operations, &info.sender)
+}
+
+fn unpause(
+    deps: DepsMut,
+    env: Env,
+    operations: Option<Vec<Operation>>,
+    sender: &Addr,
+) -> Result<Response, ContractError> {
+    let mut pause = PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    let event = match operations {
+        Some(operations) => {
+            pause.operations.retain(|op| !operations.contains(op));
+            pause_event("unpause", &operations)
+        }
+        None => {
+            pause = PauseState::default();
+            Event::new("unpause").add_attribute("scope", "all")
+        }
+    };
+    PAUSE.save(deps.storage, &pause)?;
+    record_pauses(deps.storage, "", env.block.time.seconds(), &pause)?;
+
+    Ok(Response::new()
+        .add_event(event.add_attribute("pauser", sender))
+        .add_attribute("action", "unpause")
+        .add_attribute("pauser", sender))
+}
+
+pub fn execute_pause_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), &info, Role::Pauser)?;
+    // pausing is an emergency action, lifting it is governed
+    if !paused {
+        assert_no_governance_delay(deps.storage)?;
+    }
+
+    set_asset_paused(deps, env, contract, paused, &info.sender)
+}
+
+fn set_asset_paused(
+    deps: DepsMut,
+    env: Env,
+    contract: String,
+    paused: bool,
+    sender: &Addr,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let action = if paused {
+        PAUSED_ASSETS.save(deps.storage, &contract, &Empty {})?;
+        "pause_asset"
+    } else {
+        PAUSED_ASSETS.remove(deps.storage, &contract);
+        "unpause_asset"
+    };
+    let now = env.block.time.seconds();
+    record_pause(
+        deps.storage,
+        &SETTLEMENT_PAUSES,
+        contract.as_str(),
+        now,
+        paused,
+    )?;
+    record_pause(deps.storage, &BUY_PAUSES, contract.as_str(), now, paused)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new(action)
+                .add_attribute("rwa_contract", contract.clone())
+                .add_attribute("pauser", sender),
+        )
+        .add_attribute("action", action)
+        .add_attribute("rwa_contract", contract)
+        .add_attribute("pauser", sender))
+}
+
+/// Records the contract-wide settlement and buy pause spans after `pause` changed.
+fn record_pauses(
+    store: &mut dyn Storage,
+    scope: &str,
+    now: u64,
+    pause: &PauseState,
+) -> StdResult<()> {
+    record_pause(
+        store,
+        &SETTLEMENT_PAUSES,
+        scope,
+        now,
+        pause.halts_settlement(),
+    )?;
+    record_pause(store, &BUY_PAUSES, scope, now, pause.halts_buy())
+}
+
+/// Opens or closes a span in `pauses` when `halted` changes for `scope`.
+fn record_pause(
+    store: &mut dyn Storage,
+    pauses: &Map<(&str, u64), u64>,
+    scope: &str,
+    now: u64,
+    halted: bool,
+) -> StdResult<()> {
+    let ongoing = pauses
+        .prefix(scope)
+        .range(store, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .filter(|(_, end)| *end == u64::MAX);
+    match (ongoing, halted) {
+        (None, true) => pauses.save(store, (scope, now), &u64::MAX),
+        (Some((start, _)), false) => pauses.save(store, (scope, start), &now),
+        _ => Ok(()),
+    }
+}
+
+/// Seconds within `[start, end)` during which `contract` was paused per `pauses`.
+fn paused_seconds(
+    store: &dyn Storage,
+    pauses: &Map<(&str, u64), u64>,
+    contract: &Addr,
+    start: u64,
+    end: u64,
+) -> StdResult<u64> {
+    let mut spans = vec![];
+    for scope in ["", contract.as_str()] {
+        let pauses =
+            pauses
+                .prefix(scope)
+                .range(store, None, Some(Bound::exclusive(end)), Order::Descending);
+        for item in pauses {
+            let (from, to) = item?;
+            if to <= start {
+                break;
+            }
+            spans.push((from.max(start), to.min(end)));
+        }
+    }
+
+    // contract-wide and asset pauses may overlap
+    spans.sort_unstable();
+    let (mut paused, mut covered) = (0, start);
+    for (from, to) in spans {
+        let from = from.max(covered);
+        if to > from {
+            paused += to - from;
+            covered = to;
+        }
+    }
+    Ok(paused)
+}
+
+fn pause_event(ty: &str, operations: &[Operation]) -> Event {
+    let scope = operations
+        .iter()
+        .map(|op| op.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    Event::new(ty).add_attribute("scope", scope)
+}
+
+fn assert_not_paused(
+    store: &dyn Storage,
+    operation: Operation,
+    contract: &Addr,
+) -> Result<(), ContractError> {
+    let pause = PAUSE.may_load(store)?.unwrap_or_default();
+    if pause.all || pause.operations.contains(&operation) || PAUSED_ASSETS.has(store, contract) {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+pub fn execute_propose_action(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: GovernanceAction,
+) -> Result<Response, ContractError> {
+    match &action {
+        GovernanceAction::ChangeFee { .. }
+        | GovernanceAction::SetDefaultFees { .. }
+        | GovernanceAction::SetAssetFees { .. }
+        | GovernanceAction::SetDenomFees { .. }
+        | GovernanceAction::SetFeeRecipients { .. }
+        | GovernanceAction::SetKeeperBounty { .. } => {
+            only_role(deps.as_ref(), &info, Role::FeeManager)?
+        }
+        GovernanceAction::GrantRole { .. }
+        | GovernanceAction::RevokeRole { .. }
+        | GovernanceAction::SetGovernanceDelay { .. } => only_owner(deps.as_ref(), &info)?,
+        GovernanceAction::DelistAsset { .. } | GovernanceAction::SetAssetValuation { .. } => {
+            only_role(deps.as_ref(), &info, Role::AssetCurator)?
+        }
+        GovernanceAction::Unpause { .. } | GovernanceAction::UnpauseAsset { .. } => {
+            only_role(deps.as_ref(), &info, Role::Pauser)?
+        }
+    }
+
+    let mut config = GOVERNANCE.load(deps.storage)?;
+    config.num_proposals += 1;
+    let id = config.num_proposals;
+    GOVERNANCE.save(deps.storage, &config)?;
+
+    let eta = env.block.time.seconds() + config.delay;
+    let proposal = Proposal {
+        action,
+        proposer: info.sender.clone(),
+        eta,
+    };
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_action")
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("proposer", info.sender)
+        .add_attribute("eta", eta.to_string()))
+}
+
+pub fn execute_cancel_action(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let proposal = PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::ProposalNotFound {})?;
+    if proposal.proposer.ne(&info.sender) {
+        only_owner(deps.as_ref(), &info)?;
+    }
+
+    PROPOSALS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_action")
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("canceller", info.sender))
+}
+
+/// Anyone can execute a proposal once its delay has passed.
+pub fn execute_execute_action(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let proposal = PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::ProposalNotFound {})?;
+    if env.block.time.seconds() < proposal.eta {
+        return Err(ContractError::ProposalNotReady {});
+    }
+    PROPOSALS.remove(deps.storage, id);
+
+    let res = match proposal.action {
+        GovernanceAction::ChangeFee { fee } => change_fee(deps, env, fee)?,
+        GovernanceAction::SetDefaultFees { rates } => set_default_fees(deps, env, rates)?,
+        GovernanceAction::SetAssetFees { contract, rates } => {
+            set_asset_fees(deps, env, contract, rates)?
+        }
+        GovernanceAction::SetDenomFees {
+            denom,
+            rates,
+            min_fee,
+            tiers,
+        } => set_denom_fees(deps, env, denom, rates, min_fee, tiers)?,
+        GovernanceAction::SetFeeRecipients {
+            recipients,
+            referrer_share,
+        } => set_fee_recipients(deps, recipients, referrer_share)?,
+        GovernanceAction::SetKeeperBounty { bounty } => set_keeper_bounty(deps, bounty)?,
+        GovernanceAction::SetAssetValuation { contract, price } => {
+            set_asset_valuation(deps, contract, price)?
+        }
+        GovernanceAction::GrantRole { role, address } => grant_role(deps, role, address)?,
+        GovernanceAction::RevokeRole { role, address } => revoke_role(deps, role, address)?,
+        GovernanceAction::DelistAsset { contract } => {
+            set_asset_paused(deps, env, contract, true, &proposal.proposer)?
+        }
+        GovernanceAction::Unpause { operations } => {
+            unpause(deps, env, operations, &proposal.proposer)?
+        }
+        GovernanceAction::UnpauseAsset { contract } => {
+            set_asset_paused(deps, env, contract, false, &proposal.proposer)?
+        }
+        GovernanceAction::SetGovernanceDelay { delay } => {
+            GOVERNANCE.update(deps.storage, |mut config| -> StdResult<_> {
+                config.delay = delay;
+                Ok(config)
+            })?;
+            Response::new()
+                .add_attribute("action", "set_governance_delay")
+                .add_attribute("delay", delay.to_string())
+        }
+    };
+
+    Ok(res
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("executor", info.sender))
+}
+
+/// Governed actions must go through the proposal queue once a delay is configured.
+fn assert_no_governance_delay(store: &dyn Storage) -> Result<(), ContractError> {
+    if GOVERNANCE.load(store)?.delay > 0 {
+        return Err(ContractError::TimelockRequired {});
+    }
+    Ok(())
+}
+
+fn only_owner(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.owner.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Passes for the owner and for holders of `role`.
+fn only_role(deps: Deps, info: &MessageInfo, role: Role) -> Result<(), ContractError> {
+    if ROLES.has(deps.storage, (role.as_str(), &info.sender)) {
+        return Ok(());
+    }
+    only_owner(deps, info)
+}
+
+pub enum FeeKind {
+    Buy,
+    Rent,
+}
+
+/// Resolves the fee `trader` pays on `amount` for an RWA from `contract`.
+/// Asset overrides win over denom overrides, which win over the default
+/// tier; the trader's volume discount and the denom's fee floor apply last.
+/// The floor never lifts the fee above the maximum rate.
+pub fn compute_fee(
+    store: &dyn Storage,
+    now: u64,
+    kind: FeeKind,
+    contract: &Addr,
+    trader: Option<&Addr>,
+    amount: &Coin,
+) -> StdResult<Uint128> {
+    let denom_fees = DENOM_FEES
+        .may_load(store, &amount.denom)?
+        .map(|fees| fees.active(now).clone());
+    let asset_rates = ASSET_FEES
+        .may_load(store, contract)?
+        .and_then(|fees| fees.active(now).clone());
+    let rates = match asset_rates {
+        Some(rates) => rates,
+        None => match denom_fees.as_ref().and_then(|fees| fees.rates.clone()) {
+            Some(rates) => rates,
+            None => FEE_SCHEDULE.load(store)?.default.active(now).clone(),
+        },
+    };
+    let rate = match kind {
+        FeeKind::Buy => rates.buy,
+        FeeKind::Rent => rates.rent,
+    };
+
+    let Some(denom_fees) = denom_fees else {
+        return Ok(amount.amount * rate);
+    };
+
+    let volume = match trader {
+        Some(trader) => TRADER_VOLUME
+            .may_load(store, (trader, &amount.denom))?
+            .unwrap_or_default(),
+        None => Uint128::zero(),
+    };
+    let discount = denom_fees
+        .tiers
+        .iter()
+        .rev()
+        .find(|tier| volume >= tier.min_volume)
+        .map(|tier| tier.discount)
+        .unwrap_or_default();
+
+    let fee = amount.amount * (rate * (Decimal::one() - discount));
+    let max_fee = amount.amount * FEE_SCHEDULE.load(store)?.max_fee;
+    Ok(fee.max(denom_fees.min_fee).min(max_fee))
+}
+
+fn record_volume(
+    store: &mut dyn Storage,
+    trader: &Addr,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    TRADER_VOLUME.update(store, (trader, denom), |volume| -> StdResult<_> {
+        Ok(volume.unwrap_or_default().checked_add(amount)?)
+    })?;
+    Ok(())
+}
+
+/// Pays out the referrer's and recipients' shares of a collected fee; the
+/// remainder stays in the contract.
+fn fee_messages(
+    store: &mut dyn Storage,
+    fee: Coin,
+    referrer: Option<&Addr>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let schedule = FEE_SCHEDULE.load(store)?;
+
+    let mut payouts: Vec<(Addr, Uint128)> = schedule
+        .recipients
+        .into_iter()
+        .map(|r| (r.address, fee.amount * r.share))
+        .collect();
+    if let Some(referrer) = referrer {
+        payouts.push((referrer.clone(), fee.amount * schedule.referrer_share));
+    }
+    let paid_out: Uint128 = payouts.iter().map(|(_, amount)| amount).sum();
+    COLLECTED_FEES.update(store, &fee.denom, |collected| -> StdResult<_> {
+        Ok(collected.unwrap_or_default() + fee.amount - paid_out)
+    })?;
+
+    Ok(payouts
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(to_address, amount)| {
+            BankMsg::Send {
+                to_address: to_address.into(),
+                amount: vec![coin(amount.u128(), &fee.denom)],
+            }
+            .into()
+        })
+        .collect())
+}
+
+/// Adds `amount` to the asset's cumulative income-per-share accumulator.
+fn distribute_income(asset: &mut Asset, amount: Uint128) -> Result<(), ContractError> {
+    if asset.total_shares.is_zero() {
+        return Err(ContractError::NoHolders {});
+    }
+
+    let per_share = Decimal::checked_from_ratio(amount, asset.total_shares)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    asset.income_per_share += per_share;
+    Ok(())
+}
+
+/// Moves income accrued since the holder's last snapshot into `pending`.
+fn settle_holder(asset: &Asset, holder: &mut Holder) {
+    holder.pending += holder.shares * (asset.income_per_share - holder.income_per_share);
+    holder.income_per_share = asset.income_per_share;
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetCount {} => to_json_binary(&query_count(deps)?),
+        QueryMsg::GetFee {} => to_json_binary(&query_fee(deps, env)?),
+        QueryMsg::AllOffers {
+            start_after,
+            limit,
+            viewer,
+        } => to_json_binary(&query_all(deps, start_after, limit, viewer)?),
+        QueryMsg::GetPendingBundle { offering_id } => {
+            to_json_binary(&query_pending_bundle(deps, offering_id)?)
+        }
+        QueryMsg::GetRental { rental_id } => to_json_binary(&query_rental(deps, env, rental_id)?),
+        QueryMsg::Bookings {
+            offering_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query_bookings(deps, offering_id, start_after, limit)?),
+        QueryMsg::GetAsset { contract } => to_json_binary(&query_asset(deps, contract)?),
+        QueryMsg::GetPool { denom } => to_json_binary(&query_pool(deps, denom)?),
+        QueryMsg::LenderBalance { denom, lender } => {
+            to_json_binary(&query_lender_balance(deps, denom, lender)?)
+        }
+        QueryMsg::GetLoan { loan_id } => to_json_binary(&query_loan(deps, env, loan_id)?),
+        QueryMsg::GetRedemptionWindow { window_id } => {
+            to_json_binary(&query_redemption_window(deps, window_id)?)
+        }
+        QueryMsg::RedemptionRequest { window_id, holder } => {
+            to_json_binary(&query_redemption_request(deps, window_id, holder)?)
+        }
+        QueryMsg::GetInstallmentPlan { offering_id } => {
+            to_json_binary(&query_installment_plan(deps, env, offering_id)?)
+        }
+        QueryMsg::PendingIncome { contract, holder } => {
+            to_json_binary(&query_pending_income(deps, contract, holder)?)
+        }
+        QueryMsg::QuoteBuy {
+            offering_id,
+            buyer,
+            amount,
+        } => to_json_binary(
+            &query_quote_buy(deps, env, offering_id, buyer, amount)
+                .map_err(|e| StdError::generic_err(e.to_string()))?,
+        ),
+        QueryMsg::SimulateBuy {
+            offering_id,
+            amount,
+            buyer,
+        } => to_json_binary(
+            &query_quote_buy(deps, env, offering_id, buyer, Some(amount))
+                .map_err(|e| StdError::generic_err(e.to_string()))?,
+        ),
+        QueryMsg::SimulateRent {
+            offering_id,
+            duration,
+            renter,
+        } => to_json_binary(
+            &query_simulate_rent(deps, env, offering_id, duration, renter)
+                .map_err(|e| StdError::generic_err(e.to_string()))?,
+        ),
+        QueryMsg::ExpiredRentals { start_after, limit } => {
+            to_json_binary(&query_expired_rentals(deps, env, start_after, limit)?)
+        }
+        QueryMsg::GetFeeSchedule {} => to_json_binary(&query_fee_schedule(deps, env)?),
+        QueryMsg::GetAssetFees { contract } => {
+            to_json_binary(&query_asset_fees(deps, env, contract)?)
+        }
+        QueryMsg::GetDenomFees { denom } => to_json_binary(&query_denom_fees(deps, env, denom)?),
+        QueryMsg::TraderVolume { trader, denom } => {
+            to_json_binary(&query_trader_volume(deps, trader, denom)?)
+        }
+        QueryMsg::CollectedFees { denom } => to_json_binary(&query_collected_fees(deps, denom)?),
+        QueryMsg::Ownership {} => to_json_binary(&query_ownership(deps)?),
+        QueryMsg::Roles { address } => to_json_binary(&query_roles(deps, address)?),
+        QueryMsg::PauseStatus {} => to_json_binary(&query_pause_status(deps)?),
+        QueryMsg::PendingAction { id } => to_json_binary(&query_pending_action(deps, id)?),
+        QueryMsg::PendingActions { start_after, limit } => {
+            to_json_binary(&query_pending_actions(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn query_count(deps: Deps) -> StdResult<CountResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(CountResponse {
+        count: state.num_offerings,
+    })
+}
+
+fn query_fee(deps: Deps, env: Env) -> StdResult<FeeResponse> {
+    let schedule = FEE_SCHEDULE.load(deps.storage)?;
+    Ok(FeeResponse {
+        fee: schedule.default.active(env.block.time.seconds()).buy,
+    })
+}
+
+fn query_expired_rentals(
+    deps: Deps,
+    env: Env,
+    start_after: Option<ExpiryKey>,
+    limit: Option<u32>,
+) -> StdResult<ExpiredRentalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let now = env.block.time.seconds();
+
+    let mut count = 0;
+    let mut last = start_after.map(|key| (key.end_time, key.rental_id));
+    for _ in 0..limit {
+        let Some(key) = next_expired(deps.storage, now, last.as_ref())? else {
+            last = None;
+            break;
+        };
+        if let Some(rental) = RENTALS.may_load(deps.storage, &key.1)? {
+            if sweepable(deps.storage, &rental)?.is_some() {
+                count += 1;
+            }
+        }
+        last = Some(key);
+    }
+
+    Ok(ExpiredRentalsResponse {
+        count,
+        last_scanned: last.map(|(end_time, rental_id)| ExpiryKey {
+            end_time,
+            rental_id,
+        }),
+    })
+}
+
+fn query_fee_schedule(deps: Deps, env: Env) -> StdResult<FeeScheduleResponse> {
+    let now = env.block.time.seconds();
+    let schedule = FEE_SCHEDULE.load(deps.storage)?;
+    Ok(FeeScheduleResponse {
+        default: schedule.default.active(now).clone(),
+        pending_default: pending_change(schedule.default, now),
+        max_fee: schedule.max_fee,
+        fee_timelock: schedule.fee_timelock,
+        recipients: schedule
+            .recipients
+            .into_iter()
+            .map(|r| FeeRecipientInfo {
+                address: r.address,
+                share: r.share,
+            })
+            .collect(),
+        referrer_share: schedule.referrer_share,
+        keeper_bounty: schedule.keeper_bounty,
+    })
+}
+
+fn query_asset_fees(deps: Deps, env: Env, contract: String) -> StdResult<AssetFeesResponse> {
+    let now = env.block.time.seconds();
+    let contract = deps.api.addr_validate(&contract)?;
+    let fees = ASSET_FEES
+        .may_load(deps.storage, &contract)?
+        .unwrap_or_else(|| Timelocked::new(None));
+    Ok(AssetFeesResponse {
+        rates: fees.active(now).clone(),
+        pending: pending_change(fees, now),
+    })
+}
+
+fn query_denom_fees(deps: Deps, env: Env, denom: String) -> StdResult<DenomFeesResponse> {
+    let now = env.block.time.seconds();
+    let fees = DENOM_FEES
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_else(|| Timelocked::new(DenomFees::default()));
+    let active = fees.active(now).clone();
+    Ok(DenomFeesResponse {
+        rates: active.rates,
+        min_fee: active.min_fee,
+        tiers: active.tiers,
+        pending: pending_change(fees, now),
+    })
+}
+
+/// The change still waiting on its timelock, if any.
+fn pending_change<T>(fees: Timelocked<T>, now: u64) -> Option<PendingChange<T>> {
+    fees.pending.filter(|pending| pending.effective_at > now)
+}
+
+fn query_trader_volume(
+    deps: Deps,
+    trader: String,
+    denom: String,
+) -> StdResult<TraderVolumeResponse> {
+    let trader = deps.api.addr_validate(&trader)?;
+    let volume = TRADER_VOLUME
+        .may_load(deps.storage, (&trader, &denom))?
+        .unwrap_or_default();
+    Ok(TraderVolumeResponse {
+        volume: coin(volume.u128(), denom),
+    })
+}
+
+fn query_collected_fees(deps: Deps, denom: String) -> StdResult<CollectedFeesResponse> {
+    let collected = COLLECTED_FEES
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default();
+    Ok(CollectedFeesResponse {
+        collected: coin(collected.u128(), denom),
+    })
+}
+
+fn query_ownership(deps: Deps) -> StdResult<OwnershipResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(OwnershipResponse {
+        owner: state.owner,
+        pending_owner: state.pending_owner,
+    })
+}
+
+fn query_roles(deps: Deps, address: String) -> StdResult<RolesResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let roles = Role::all()
+        .into_iter()
+        .filter(|role| ROLES.has(deps.storage, (role.as_str(), &address)))
+        .collect();
+    Ok(RolesResponse { roles })
+}
+
+fn query_pause_status(deps: Deps) -> StdResult<PauseStatusResponse> {
+    let pause = PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    let assets = PAUSED_ASSETS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PauseStatusResponse {
+        all: pause.all,
+        operations: pause.operations,
+        assets,
+    })
+}
+
+fn query_pending_action(deps: Deps, id: u64) -> StdResult<PendingActionResponse> {
+    let proposal = PROPOSALS.load(deps.storage, id)?;
+    Ok(PendingActionResponse {
+        action: map_proposal((id, proposal)),
+    })
+}
+
+fn query_pending_actions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingActionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let actions: StdResult<Vec<ProposalInfo>> = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(map_proposal))
+        .collect();
+
+    Ok(PendingActionsResponse { actions: actions? })
+}
+
+fn map_proposal((id, proposal): (u64, Proposal)) -> ProposalInfo {
+    ProposalInfo {
+        id,
+        action: proposal.action,
+        proposer: proposal.proposer,
+        eta: proposal.eta,
+    }
+}
+
+fn query_all(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    viewer: Option<String>,
+) -> StdResult<OffersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+    let viewer = maybe_addr(deps.api, viewer)?;
+
+    let offers: StdResult<Vec<Offer>> = OFFERINGS
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, off)| off.visible_to(viewer.as_ref()))
+        })
+        .take(limit)
+        .map(|item| item.map(map_offer))
+        .collect();
+
+    Ok(OffersResponse { offers: offers? })
+}
+
+fn query_pending_bundle(deps: Deps, offering_id: String) -> StdResult<PendingBundleResponse> {
+    let bundle = PENDING_BUNDLES.load(deps.storage, &offering_id)?;
+    Ok(PendingBundleResponse {
+        seller: bundle.seller,
+        list_price: bundle.list_price,
+        assets: bundle.assets,
+        deposited: bundle.deposited,
+    })
+}
+
+fn query_bookings(
+    deps: Deps,
+    offering_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<BookingsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let bookings = BOOKINGS
+        .prefix(&offering_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, rental_id) = item?;
+            let rental = RENTALS.load(deps.storage, &rental_id)?;
+            Ok(Booking {
+                rental_id,
+                renter: rental.renter,
+                start_time: rental.start_time,
+                end_time: rental.end_time,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(BookingsResponse { bookings })
+}
+
+fn query_rental(deps: Deps, env: Env, rental_id: String) -> StdResult<RentalResponse> {
+    let rental = RENTALS.load(deps.storage, &rental_id)?;
+    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
+    let now = env.block.time.seconds();
+    let claimable = claimable(deps.storage, now, &off, &rental)?;
+    let owed = late_fee(deps.storage, &off.contract, &rental, now)?;
+    let denom = off.rental_terms.rate.denom;
+    Ok(RentalResponse {
+        rental: RentalInfo {
+            id: rental.id,
+            offering_id: rental.offering_id,
+            renter: rental.renter,
+            start_time: rental.start_time,
+            end_time: rental.end_time,
+            amount: off.amount,
+            escrow: coin(rental.escrow.u128(), &denom),
+            paid_until: rental.paid_until,
+            claimed: coin(rental.claimed.u128(), &denom),
+            claimable: coin(claimable.u128(), &denom),
+            active: rental.active,
+            lessor: rental.lessor,
+            parent_rental_id: rental.parent_rental_id,
+            sublet_offering_id: rental.sublet_offering_id,
+            equity: coin(rental.equity.u128(), &denom),
+            equity_rate: rental.equity_rate,
+            early_termination_penalty: rental.early_termination_penalty,
+            returned_at: rental.returned_at,
+            deposit: coin(rental.deposit.u128(), &denom),
+            amount_owed: coin(owed.u128(), denom),
+        },
+    })
+}
+
+fn query_redemption_window(deps: Deps, window_id: u64) -> StdResult<RedemptionWindowResponse> {
+    let window = REDEMPTIONS.load(deps.storage, window_id)?;
+    Ok(RedemptionWindowResponse {
+        contract: window.contract,
+        issuer: window.issuer,
+        price: window.price,
+        cap: window.cap,
+        requested: window.requested,
+        closes_at: window.closes_at,
+        closed: window.closed,
+    })
+}
+
+fn query_redemption_request(
+    deps: Deps,
+    window_id: u64,
+    holder: String,
+) -> StdResult<RedemptionRequestResponse> {
+    let holder = deps.api.addr_validate(&holder)?;
+    let window = REDEMPTIONS.load(deps.storage, window_id)?;
+    let tokens = REDEMPTION_REQUESTS
+        .may_load(deps.storage, (window_id, &holder))?
+        .unwrap_or_default();
+    let filled = window.fill(tokens);
+    Ok(RedemptionRequestResponse {
+        tokens,
+        filled,
+        payout: coin((filled * window.price.amount).u128(), window.price.denom),
+    })
+}
+
+fn query_installment_plan(
+    deps: Deps,
+    env: Env,
+    offering_id: String,
+) -> StdResult<InstallmentPlanResponse> {
+    let plan = INSTALLMENT_PLANS.load(deps.storage, &offering_id)?;
+    let terms = &plan.terms;
+    let off = &plan.offering;
+    let price = off.list_price.amount;
+
+    let installment = terms.installment(price);
+    let covered = match (plan.paid - terms.down_payment(price)).checked_div(installment) {
+        Ok(covered) => covered.u128().min(terms.installments.into()) as u64,
+        Err(_) => terms.installments.into(),
+    };
+    let now = env.block.time.seconds();
+    let paused = paused_seconds(
+        deps.storage,
+        &BUY_PAUSES,
+        &off.contract,
+        plan.start_time,
+        now,
+    )?;
+    let next_due = plan.start_time + (covered + 1) * terms.interval + paused;
+    let next_payment = terms
+        .due(price, (covered + 1) * terms.interval)
+        .saturating_sub(plan.paid);
+
+    Ok(InstallmentPlanResponse {
+        buyer: plan.buyer.clone(),
+        seller: off.seller.clone(),
+        contract: off.contract.clone(),
+        amount: off.amount,
+        price: off.list_price.clone(),
+        paid: coin(plan.paid.u128(), &off.list_price.denom),
+        next_due,
+        next_payment: coin(next_payment.u128(), &off.list_price.denom),
+        defaulted: plan.defaulted(now, paused),
+    })
+}
+
+fn query_pool(deps: Deps, denom: String) -> StdResult<PoolResponse> {
+    let pool = POOLS.may_load(deps.storage, &denom)?.unwrap_or_default();
+    Ok(PoolResponse {
+        liquidity: coin(pool.liquidity.u128(), &denom),
+        borrowed: coin(pool.borrowed.u128(), denom),
+        total_shares: pool.total_shares,
+    })
+}
+
+fn query_lender_balance(deps: Deps, denom: String, lender: String) -> StdResult<LenderResponse> {
+    let lender = deps.api.addr_validate(&lender)?;
+    let pool = POOLS.may_load(deps.storage, &denom)?.unwrap_or_default();
+    let shares = POOL_SHARES
+        .may_load(deps.storage, (&denom, &lender))?
+        .unwrap_or_default();
+    let value = if pool.total_shares.is_zero() {
+        Uint128::zero()
+    } else {
+        shares.multiply_ratio(pool.value(), pool.total_shares)
+    };
+    Ok(LenderResponse {
+        shares,
+        value: coin(value.u128(), denom),
+    })
+}
+
+fn query_loan(deps: Deps, env: Env, loan_id: u64) -> StdResult<LoanResponse> {
+    let loan = LOANS.load(deps.storage, loan_id)?;
+    let asset = ASSETS.load(deps.storage, &loan.contract)?;
+    let debt = loan.debt + interest_due(&loan, env.block.time.seconds());
+    let value = collateral_value(&asset, &loan);
+    let liquidatable = loan.offering_id.is_none()
+        && value.is_some_and(|value| debt > value * loan.terms.liquidation_ltv);
+    let value = value.unwrap_or_default();
+    Ok(LoanResponse {
+        borrower: loan.borrower,
+        contract: loan.contract,
+        collateral: loan.collateral,
+        debt: coin(debt.u128(), &loan.denom),
+        collateral_value: coin(value.u128(), loan.denom),
+        liquidatable,
+        offering_id: loan.offering_id,
+        terms: loan.terms,
+    })
+}
+
+fn query_asset(deps: Deps, contract: String) -> StdResult<AssetResponse> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let asset = ASSETS.load(deps.storage, &contract)?;
+    Ok(AssetResponse {
+        contract,
+        issuer: asset.issuer,
+        income_denom: asset.income_denom,
+        total_shares: asset.total_shares,
+        income_per_share: asset.income_per_share,
+        royalty_recipient: asset.royalty.as_ref().map(|r| r.recipient.clone()),
+        royalty_rate: asset.royalty.map(|r| r.rate).unwrap_or_default(),
+        valuation: asset.valuation,
+        lending: asset.lending,
+    })
+}
+
+fn query_quote_buy(
+    deps: Deps,
+    env: Env,
+    offering_id: String,
+    buyer: Option<String>,
+    amount: Option<Uint128>,
+) -> Result<QuoteResponse, ContractError> {
+    let off = OFFERINGS.load(deps.storage, &offering_id)?;
+    // without a buyer the quote uses the undiscounted rate
+    let buyer = maybe_addr(deps.api, buyer)?;
+    let now = env.block.time.seconds();
+    match &buyer {
+        Some(buyer) => assert_buyable(deps.storage, &offering_id, &off, buyer)?,
+        None if !off.listing_type.for_sale() => return Err(ContractError::NotForSale {}),
+        None if !off.visible_to(None) => return Err(ContractError::PrivateListing {}),
+        None => {}
+    }
+    let price = off.list_price.amount;
+    let amount = amount.unwrap_or(price);
+    if amount < price {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    let quote = quote_sale(deps.storage, now, &off, price, buyer.as_ref())?;
+
+    let denom = &off.list_price.denom;
+    Ok(QuoteResponse {
+        price: off.list_price.clone(),
+        protocol_fee: coin(quote.protocol_fee.u128(), denom),
+        royalty: coin(quote.royalty.u128(), denom),
+        royalties: quote
+            .royalties
+            .into_iter()
+            .map(|(recipient, amount)| RoyaltyPayment {
+                recipient,
+                amount: coin(amount.u128(), denom),
+            })
+            .collect(),
+        seller_amount: coin(quote.seller_amount.u128(), denom),
+        refund: coin((amount - price).u128(), denom),
+    })
+}
+
+fn query_simulate_rent(
+    deps: Deps,
+    env: Env,
+    offering_id: String,
+    duration: u64,
+    renter: Option<String>,
+) -> Result<SimulateRentResponse, ContractError> {
+    let off = OFFERINGS.load(deps.storage, &offering_id)?;
+    let renter = maybe_addr(deps.api, renter)?;
+
+    // simulates renting from now, as RentRwa does without a reservation
+    let now = env.block.time.seconds();
+    assert_rentable(
+        deps.storage,
+        now,
+        &offering_id,
+        &off,
+        renter.as_ref(),
+        now,
+        duration,
+    )?;
+
+    let terms = &off.rental_terms;
+    let price = terms.price(duration)?;
+    let equity = price * off.equity_rate;
+    let fee = rent_fee(deps.storage, now, &off, renter.as_ref(), price - equity)?;
+
+    let denom = &terms.rate.denom;
+    Ok(SimulateRentResponse {
+        price: coin(price.u128(), denom),
+        protocol_fee: coin(fee.u128(), denom),
+        equity: coin(equity.u128(), denom),
+        seller_amount: coin((price - equity - fee).u128(), denom),
+        deposit: coin(off.security_deposit.u128(), denom),
+    })
+}
+
+fn query_pending_income(
+    deps: Deps,
+    contract: String,
+    holder: String,
+) -> StdResult<PendingIncomeResponse> {
+    let contract = deps.api.addr_validate(&contract)?;
+    let holder = deps.api.addr_validate(&holder)?;
+    let asset = ASSETS.load(deps.storage, &contract)?;
+
+    let (shares, pending) = match HOLDERS.may_load(deps.storage, (&contract, &holder))? {
+        Some(mut holder) => {
+            settle_holder(&asset, &mut holder);
+            (holder.shares, holder.pending)
+        }
+        None => (Uint128::zero(), Uint128::zero()),
+    };
+
+    Ok(PendingIncomeResponse {
+        shares,
+        pending: coin(pending.u128(), asset.income_denom),
+    })
+}
+
+fn map_offer((k, v): (String, Offering)) -> Offer {
+    Offer {
+        id: k,
+        amount: v.amount,
+        contract: v.contract,
+        seller: v.seller,
+        list_price: v.list_price,
+        listing_type: v.listing_type,
+        rental_terms: v.rental_terms,
+        renewals_enabled: v.renewals_enabled,
+        early_termination_penalty: v.early_termination_penalty,
+        allow_sublet: v.allow_sublet,
+        sublet_cut: v.sublet_cut,
+        parent_rental_id: v.parent_rental_id,
+        equity_rate: v.equity_rate,
+        grace_period: v.grace_period,
+        late_fee_rate: v.late_fee_rate,
+        security_deposit: v.security_deposit,
+        loan_id: v.loan_id,
+        installment_terms: v.installment_terms,
+        allowed_buyers: v.allowed_buyers,
+        bundle: v.bundle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::SellLot;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
+    };
+    use cosmwasm_std::{attr, coins, Decimal, SubMsg};
+    use cw20::Cw20ReceiveMsg;
+
+    fn setup(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            fee: Decimal::percent(2),
+            max_fee: Decimal::percent(10),
+            fee_timelock: 86400,
+            governance_delay: 0,
+        };
+        let info = mock_info("creator", &[]);
+
+        // we can just call .unwrap() to assert this was a success
+        instantiate(deps, mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            fee: Decimal::percent(2),
+            max_fee: Decimal::percent(10),
+            fee_timelock: 86400,
+            governance_delay: 0,
+        };
+        let info = mock_info("creator", &[]);
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+    }
+
+    #[test]
+    fn sell_rwa() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let sell_msg = SellRwa {
+            list_price: coin(1000, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        let info = mock_info("rwa-token", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(0, res.messages.len());
+
+        let msg = QueryMsg::AllOffers {
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: OffersResponse = from_json(&res).unwrap();
+
+        assert_eq!(Uint128::new(100), value.offers.first().unwrap().amount);
+    }
+
+    #[test]
+    fn receive_hook() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: Binary::default(),
+        });
+        let info = mock_info("rwa-token", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::NoData {} => {}
+            _ => panic!("Must return no data error"),
+        }
+
+        // a bare listing without the hook name is rejected
+        let sell_msg = SellRwa {
+            list_price: coin(1000, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&sell_msg).unwrap(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        match err {
+            ContractError::Std(StdError::ParseErr { .. }) => {}
+            _ => panic!("Must return parse error"),
+        }
+    }
+
+    #[test]
+    fn buy_rwa() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let sell_msg = SellRwa {
+            list_price: coin(1000, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        let info = mock_info("rwa-token", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Now buy the RWA tokens
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(980, "earth")
+            }))
+        );
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "rwa-token".into(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: "buyer".into(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))
+        );
+
+        // Check that the offering has been removed
+        let msg = QueryMsg::AllOffers {
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: OffersResponse = from_json(&res).unwrap();
+        assert_eq!(0, value.offers.len());
+    }
+
+    #[test]
+    fn withdraw_fees() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        setup(deps.as_mut());
+
+        // a 2% fee on a 1000earth sale
+        list_rwa(deps.as_mut(), 1000);
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = QueryMsg::CollectedFees {
+            denom: "earth".into(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: CollectedFeesResponse = from_json(&res).unwrap();
+        assert_eq!(coin(20, "earth"), value.collected);
+
+        let msg = ExecuteMsg::WithdrawFees {
+            amount: 20u32.into(),
+            denom: "earth".into(),
+        };
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+
+        // the rest of the balance isn't the protocol's to take
+        let info = mock_info("creator", &[]);
+        let over = ExecuteMsg::WithdrawFees {
+            amount: 21u32.into(),
+            denom: "earth".into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), over).unwrap_err();
+        match err {
+            ContractError::InsufficientFees {} => {}
+            _ => panic!("Must return InsufficientFees error"),
+        }
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(20, "earth")
+            }))
+        );
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    }
+
+    #[test]
+    fn change_fee() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(1),
+        };
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let msg = QueryMsg::GetFee {};
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: FeeResponse = from_json(&res).unwrap();
+        assert_eq!(Decimal::percent(1), value.fee);
+    }
+
+    #[test]
+    fn rent_rwa() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup(deps.as_mut());
+
+        // Create an offering
+        let sell_msg = SellRwa {
+            list_price: coin(10, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        let info = mock_info("rwa-token", &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // Rent the RWA
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30, // 30 seconds
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth")); // 10 * 30 = 300
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // rent is escrowed until the rental ends
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "rwa-token".into(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: "renter".into(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))
+        );
+
+        // Check rental info
+        let msg = QueryMsg::GetRental {
+            rental_id: "1".into(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let rental_info: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(rental_info.rental.renter, "renter");
+        assert_eq!(rental_info.rental.amount, Uint128::new(100));
+        assert_eq!(rental_info.rental.escrow, coin(294, "earth")); // 300 - 2% fee
+    }
+
+    #[test]
+    fn end_rental_and_clawback() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+
+        // List RWA
+        let list_price = coin(10, "earth");
+        let sell_msg = SellRwa {
+            list_price: list_price.clone(),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+        let list_msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        let list_info = mock_info("rwa-token", &[]);
+        let list_res = execute(deps.as_mut(), env.clone(), list_info, list_msg).unwrap();
+
+        // Check that the RWA was listed successfully
+        assert_eq!(0, list_res.messages.len());
+        assert_eq!(
+            list_res.attributes,
+            vec![
+                attr("action", "sell_rwa"),
+                attr("offering_id", "1"),
+                attr("rwa_contract", "rwa-token"),
+                attr("seller", "owner"),
+                attr(
+                    "list_price",
+                    format!("{}{}", list_price.amount, list_price.denom)
+                ),
+                attr("amount", "100"),
+            ]
+        );
+
+        // Rent the RWA
+        let rent_msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30, // 30 seconds
+            referrer: None,
+            max_total: None,
+        };
+        let rent_info = mock_info("renter", &coins(300, "earth")); // 10 * 30 = 300
+        execute(deps.as_mut(), env.clone(), rent_info, rent_msg).unwrap();
+
+        // Fast forward time
+        env.block.time = env.block.time.plus_seconds(31);
+
+        // the renter still holds the RWA
+        let end_msg = ExecuteMsg::EndRental {
+            rental_id: "1".into(),
+        };
+        let end_info = mock_info("renter", &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            end_info.clone(),
+            end_msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::RwaNotReturned {} => {}
+            _ => panic!("Must return RwaNotReturned error"),
+        }
+        // the owner can take the rent without the RWA back, but the rental stays open
+        let clawback_msg = ExecuteMsg::Clawback {
+            rental_id: "1".into(),
+        };
+        let clawback_info = mock_info("owner", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            clawback_info.clone(),
+            clawback_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(294, "earth")
+            })]
+        );
+        let rent_msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let rent_info = mock_info("other", &coins(300, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), rent_info, rent_msg).unwrap_err();
+        match err {
+            ContractError::RwaNotReturned {} => {}
+            _ => panic!("Must return RwaNotReturned error"),
+        }
+
+        // only the whole lot can be returned
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "renter".into(),
+            amount: Uint128::new(99),
+            msg: to_json_binary(&ReceiveHookMsg::ReturnRental(ReturnRental {
+                rental_id: "1".into(),
+            }))
+            .unwrap(),
+        });
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("rwa-token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidReturn {} => {}
+            _ => panic!("Must return InvalidReturn error"),
+        }
+        return_rental(deps.as_mut(), env.clone(), "renter", "1");
+
+        // settling leaves the returned RWA listed in the contract
+        let end_res = execute(deps.as_mut(), env.clone(), end_info, end_msg).unwrap();
+        assert!(end_res.messages.is_empty());
+        assert!(OFFERINGS.has(&deps.storage, "1"));
+
+        // Try clawback (should fail as rental is already ended)
+        let clawback_err =
+            execute(deps.as_mut(), env.clone(), clawback_info, clawback_msg).unwrap_err();
+        match clawback_err {
+            ContractError::RentalNotFound {} => {}
+            _ => panic!("Must return RentalNotFound error"),
+        }
+    }
+
+    fn return_rental(deps: DepsMut, env: Env, renter: &str, rental_id: &str) {
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: renter.into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::ReturnRental(ReturnRental {
+                rental_id: rental_id.into(),
+            }))
+            .unwrap(),
+        });
+        execute(deps, env, mock_info("rwa-token", &[]), msg).unwrap();
+    }
+
+    fn register_asset(deps: DepsMut) {
+        let msg = ExecuteMsg::RegisterAsset {
+            contract: "rwa-token".into(),
+            issuer: "issuer".into(),
+            income_denom: "earth".into(),
+        };
+        execute(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn set_shares(deps: DepsMut, holder: &str, shares: u128) {
+        let msg = ExecuteMsg::SetHolderShares {
+            contract: "rwa-token".into(),
+            holder: holder.into(),
+            shares: Uint128::new(shares),
+        };
+        execute(deps, mock_env(), mock_info("issuer", &[]), msg).unwrap();
+    }
+
+    fn pending_income(deps: Deps, holder: &str) -> Uint128 {
+        let msg = QueryMsg::PendingIncome {
+            contract: "rwa-token".into(),
+            holder: holder.into(),
+        };
+        let res = query(deps, mock_env(), msg).unwrap();
+        let value: PendingIncomeResponse = from_json(&res).unwrap();
+        value.pending.amount
+    }
+
+    #[test]
+    fn income_distribution() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // only the owner registers assets
+        let msg = ExecuteMsg::RegisterAsset {
+            contract: "rwa-token".into(),
+            issuer: "issuer".into(),
+            income_denom: "earth".into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        register_asset(deps.as_mut());
+
+        // nobody to distribute to yet
+        let msg = ExecuteMsg::DepositIncome {
+            contract: "rwa-token".into(),
+        };
+        let info = mock_info("issuer", &coins(100, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::NoHolders {} => {}
+            _ => panic!("Must return NoHolders error"),
+        }
+
+        set_shares(deps.as_mut(), "alice", 30);
+        set_shares(deps.as_mut(), "bob", 70);
+
+        let info = mock_info("issuer", &coins(100, "earth"));
+        execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        assert_eq!(Uint128::new(30), pending_income(deps.as_ref(), "alice"));
+        assert_eq!(Uint128::new(70), pending_income(deps.as_ref(), "bob"));
+
+        // re-weighting keeps income already earned
+        set_shares(deps.as_mut(), "bob", 20);
+        let info = mock_info("issuer", &coins(100, "earth"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(Uint128::new(90), pending_income(deps.as_ref(), "alice"));
+        assert_eq!(Uint128::new(110), pending_income(deps.as_ref(), "bob"));
+
+        let msg = ExecuteMsg::ClaimIncome {
+            contract: "rwa-token".into(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(90, "earth")
+            }))
+        );
+        assert_eq!(Uint128::zero(), pending_income(deps.as_ref(), "alice"));
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NothingToClaim {} => {}
+            _ => panic!("Must return NothingToClaim error"),
+        }
+    }
+
+    #[test]
+    fn rent_income_goes_to_holders() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        register_asset(deps.as_mut());
+        set_shares(deps.as_mut(), "alice", 1);
+        set_shares(deps.as_mut(), "bob", 1);
+
+        let sell_msg = SellRwa {
+            list_price: coin(10, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("rwa-token", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(Uint128::zero(), pending_income(deps.as_ref(), "alice"));
+
+        // the escrow goes to the holders once the rental ends
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(30);
+        return_rental(deps.as_mut(), env.clone(), "renter", "1");
+        let msg = ExecuteMsg::EndRental {
+            rental_id: "1".into(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("renter", &[]), msg).unwrap();
+
+        // rent stays in the contract for holders
+        assert!(res.messages.is_empty());
+        assert_eq!(Uint128::new(147), pending_income(deps.as_ref(), "alice"));
+        assert_eq!(Uint128::new(147), pending_income(deps.as_ref(), "bob"));
+    }
+
+    #[test]
+    fn royalty_on_buy() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        register_asset(deps.as_mut());
+
+        let msg = ExecuteMsg::SetRoyalty {
+            contract: "rwa-token".into(),
+            recipient: "issuer".into(),
+            rate: Decimal::percent(5),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("issuer", &[]), msg).unwrap();
+
+        // royalties are capped
+        let msg = ExecuteMsg::SetRoyalty {
+            contract: "rwa-token".into(),
+            recipient: "issuer".into(),
+            rate: Decimal::percent(50),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("issuer", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidRoyalty {} => {}
+            _ => panic!("Must return InvalidRoyalty error"),
+        }
+
+        let sell_msg = SellRwa {
+            list_price: coin(1000, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("rwa-token", &[]), msg).unwrap();
+
+        let msg = QueryMsg::QuoteBuy {
+            offering_id: "1".into(),
+            buyer: None,
+            amount: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let quote: QuoteResponse = from_json(&res).unwrap();
+        assert_eq!(coin(20, "earth"), quote.protocol_fee);
+        assert_eq!(coin(50, "earth"), quote.royalty);
+        assert_eq!(coin(930, "earth"), quote.seller_amount);
+
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(3, res.messages.len());
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(930, "earth")
+            }))
+        );
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "issuer".into(),
+                amount: coins(50, "earth")
+            }))
+        );
+        assert!(res.attributes.contains(&attr("royalty", "50")));
+    }
+
+    fn list_rwa(deps: DepsMut, price: u128) {
+        let sell_msg = SellRwa {
+            list_price: coin(price, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        execute(deps, mock_env(), mock_info("rwa-token", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn fee_schedule() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let msg = ExecuteMsg::SetFeeRecipients {
+            recipients: vec![
+                FeeRecipientMsg {
+                    address: "treasury".into(),
+                    share: Decimal::percent(50),
+                },
+                FeeRecipientMsg {
+                    address: "insurance".into(),
+                    share: Decimal::percent(30),
+                },
+            ],
+            referrer_share: Decimal::percent(30),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidFeeSplit {} => {}
+            _ => panic!("Must return InvalidFeeSplit error"),
+        }
+
+        let msg = ExecuteMsg::SetFeeRecipients {
+            recipients: vec![
+                FeeRecipientMsg {
+                    address: "treasury".into(),
+                    share: Decimal::percent(50),
+                },
+                FeeRecipientMsg {
+                    address: "insurance".into(),
+                    share: Decimal::percent(30),
+                },
+            ],
+            referrer_share: Decimal::percent(20),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // rentals of this asset are charged 10%, sales keep 5%
+        let msg = ExecuteMsg::SetAssetFees {
+            contract: "rwa-token".into(),
+            rates: Some(FeeRates {
+                buy: Decimal::percent(5),
+                rent: Decimal::percent(10),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // traders past 1000earth of volume get half off, and no fee is below 10earth
+        let msg = ExecuteMsg::SetDenomFees {
+            denom: "earth".into(),
+            rates: None,
+            min_fee: Uint128::new(10),
+            tiers: vec![VolumeTier {
+                min_volume: Uint128::new(1000),
+                discount: Decimal::percent(50),
+            }],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // the increases apply once the fee timelock has passed
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(86400);
+
+        list_rwa(deps.as_mut(), 1000);
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: Some("referrer".into()),
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(950, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "treasury".into(),
+                    amount: coins(25, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "insurance".into(),
+                    amount: coins(15, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "referrer".into(),
+                    amount: coins(10, "earth")
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "rwa-token".into(),
+                    msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                        recipient: "buyer".into(),
+                        amount: Uint128::new(100),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+            ]
+        );
+
+        let msg = QueryMsg::TraderVolume {
+            trader: "buyer".into(),
+            denom: "earth".into(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: TraderVolumeResponse = from_json(&res).unwrap();
+        assert_eq!(coin(1000, "earth"), value.volume);
+
+        // the buyer now rents at the discounted rental rate: 300 * 10% * 50%
+        list_rwa(deps.as_mut(), 10);
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "2".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("buyer", &coins(300, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.attributes.contains(&attr("protocol_fee", "15")));
+
+        // a fresh renter pays 10%, and the floor can't push that any higher
+        list_rwa(deps.as_mut(), 10);
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "3".into(),
+            duration: 5,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(50, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.attributes.contains(&attr("protocol_fee", "5")));
+
+        // the floor lifts a 5% sale fee up to the 10% maximum
+        list_rwa(deps.as_mut(), 100);
+        let msg = ExecuteMsg::Buy {
+            offering_id: "4".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("renter", &coins(100, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.attributes.contains(&attr("protocol_fee", "10")));
+
+        // ChangeFee keeps driving the default tier
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(4),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(86400);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetFeeSchedule {}).unwrap();
+        let value: FeeScheduleResponse = from_json(&res).unwrap();
+        assert_eq!(Decimal::percent(4), value.default.rent);
+        assert_eq!(2, value.recipients.len());
+    }
+
+    #[test]
+    fn fee_bounds_and_timelock() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+
+        let msg = InstantiateMsg {
+            fee: Decimal::percent(2),
+            max_fee: Decimal::one(),
+            fee_timelock: 86400,
+            governance_delay: 0,
+        };
+        let err =
+            instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidFee {} => {}
+            _ => panic!("Must return InvalidFee error"),
+        }
+
+        // the maximum fee has to leave room for the maximum royalty
+        let msg = InstantiateMsg {
+            fee: Decimal::percent(2),
+            max_fee: Decimal::percent(95),
+            fee_timelock: 86400,
+            governance_delay: 0,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+
+        // fee increases always come with notice
+        let msg = InstantiateMsg {
+            fee: Decimal::percent(2),
+            max_fee: Decimal::percent(10),
+            fee_timelock: 0,
+            governance_delay: 0,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+
+        let msg = InstantiateMsg {
+            fee: Decimal::percent(2),
+            max_fee: Decimal::percent(10),
+            fee_timelock: 86400,
+            governance_delay: 0,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(20),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidFee {} => {}
+            _ => panic!("Must return InvalidFee error"),
+        }
+
+        // increases wait for the timelock
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(5),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        list_rwa(deps.as_mut(), 1000);
+        let msg = QueryMsg::QuoteBuy {
+            offering_id: "1".into(),
+            buyer: None,
+            amount: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let quote: QuoteResponse = from_json(&res).unwrap();
+        assert_eq!(coin(20, "earth"), quote.protocol_fee);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetFeeSchedule {}).unwrap();
+        let value: FeeScheduleResponse = from_json(&res).unwrap();
+        assert_eq!(Decimal::percent(2), value.default.buy);
+        let pending = value.pending_default.unwrap();
+        assert_eq!(Decimal::percent(5), pending.value.buy);
+        assert_eq!(env.block.time.seconds() + 86400, pending.effective_at);
+
+        env.block.time = env.block.time.plus_seconds(86400);
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let quote: QuoteResponse = from_json(&res).unwrap();
+        assert_eq!(coin(50, "earth"), quote.protocol_fee);
+
+        // decreases apply at once
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(1),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetFee {}).unwrap();
+        let value: FeeResponse = from_json(&res).unwrap();
+        assert_eq!(Decimal::percent(1), value.fee);
+    }
+
+    #[test]
+    fn ownership_and_roles() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // delegate fee management
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::FeeManager,
+            address: "fee-desk".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("fee-desk", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(3),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("fee-desk", &[]), msg).unwrap();
+
+        // the fee manager cannot touch the treasury
+        let msg = ExecuteMsg::WithdrawFees {
+            amount: Uint128::new(10),
+            denom: "earth".into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("fee-desk", &[]), msg).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+
+        let msg = QueryMsg::Roles {
+            address: "fee-desk".into(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: RolesResponse = from_json(&res).unwrap();
+        assert_eq!(vec![Role::FeeManager], value.roles);
+
+        // two-step transfer
+        let msg = ExecuteMsg::ProposeOwner {
+            owner: "multisig".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::AcceptOwnership {};
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("multisig", &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap();
+        let value: OwnershipResponse = from_json(&res).unwrap();
+        assert_eq!(Some(Addr::unchecked("multisig")), value.owner);
+        assert_eq!(None, value.pending_owner);
+
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::FeeManager,
+            address: "fee-desk".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("multisig", &[]), msg).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("multisig", &[]),
+            ExecuteMsg::RenounceOwnership {},
+        )
+        .unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap();
+        let value: OwnershipResponse = from_json(&res).unwrap();
+        assert_eq!(None, value.owner);
+    }
+
+    #[test]
+    fn pause_and_circuit_breakers() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 1000);
+
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: "guardian".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Pause {
+            operations: Some(vec![Operation::Buy]),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        let res = execute(deps.as_mut(), mock_env(), mock_info("guardian", &[]), msg).unwrap();
+        assert_eq!("pause", res.events[0].ty);
+        assert!(res.events[0].attributes.contains(&attr("scope", "buy")));
+
+        let buy_msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info, buy_msg.clone()).unwrap_err();
+        match err {
+            ContractError::Paused {} => {}
+            _ => panic!("Must return Paused error"),
+        }
+
+        // listing still works while only buys are halted
+        list_rwa(deps.as_mut(), 1000);
+
+        // a compromised asset stops everything but withdrawals
+        let msg = ExecuteMsg::PauseAsset {
+            contract: "rwa-token".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("guardian", &[]), msg).unwrap();
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "2".into(),
+            duration: 1,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(1000, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        match err {
+            ContractError::Paused {} => {}
+            _ => panic!("Must return Paused error"),
+        }
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap();
+        let value: PauseStatusResponse = from_json(&res).unwrap();
+        assert!(!value.all);
+        assert_eq!(vec![Operation::Buy], value.operations);
+        assert_eq!(vec![Addr::unchecked("rwa-token")], value.assets);
+
+        // sellers can always get their lots back
+        let msg = ExecuteMsg::Pause { operations: None };
+        execute(deps.as_mut(), mock_env(), mock_info("guardian", &[]), msg).unwrap();
+        let msg = ExecuteMsg::WithdrawRwa {
+            offering_id: "1".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Unpause { operations: None };
+        execute(deps.as_mut(), mock_env(), mock_info("guardian", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UnpauseAsset {
+            contract: "rwa-token".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("guardian", &[]), msg).unwrap();
+        let buy_msg = ExecuteMsg::Buy {
+            offering_id: "2".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env(), info, buy_msg).unwrap();
+    }
+
+    #[test]
+    fn governance_timelock() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            fee: Decimal::percent(2),
+            max_fee: Decimal::percent(10),
+            fee_timelock: 86400,
+            governance_delay: 3600,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(1),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::TimelockRequired {} => {}
+            _ => panic!("Must return TimelockRequired error"),
+        }
+
+        let msg = ExecuteMsg::ProposeAction {
+            action: GovernanceAction::ChangeFee {
+                fee: Decimal::percent(1),
+            },
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = QueryMsg::PendingActions {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: PendingActionsResponse = from_json(&res).unwrap();
+        assert_eq!(2, value.actions.len());
+        assert_eq!(env.block.time.seconds() + 3600, value.actions[0].eta);
+
+        // the second proposal is withdrawn during the delay
+        let msg = ExecuteMsg::CancelAction { id: 2 };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ExecuteAction { id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::ProposalNotReady {} => {}
+            _ => panic!("Must return ProposalNotReady error"),
+        }
+
+        env.block.time = env.block.time.plus_seconds(3600);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetFee {}).unwrap();
+        let value: FeeResponse = from_json(&res).unwrap();
+        assert_eq!(Decimal::percent(1), value.fee);
+
+        let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+        match err {
+            ContractError::ProposalNotFound {} => {}
+            _ => panic!("Must return ProposalNotFound error"),
+        }
+        let msg = ExecuteMsg::ExecuteAction { id: 2 };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+        match err {
+            ContractError::ProposalNotFound {} => {}
+            _ => panic!("Must return ProposalNotFound error"),
+        }
+
+        // per-denom fee changes are queued like any other fee change
+        let msg = ExecuteMsg::SetDenomFees {
+            denom: "earth".into(),
+            rates: None,
+            min_fee: Uint128::new(5),
+            tiers: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::TimelockRequired {} => {}
+            _ => panic!("Must return TimelockRequired error"),
+        }
+        let msg = ExecuteMsg::ProposeAction {
+            action: GovernanceAction::SetDenomFees {
+                denom: "earth".into(),
+                rates: None,
+                min_fee: Uint128::new(5),
+                tiers: vec![],
+            },
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let msg = ExecuteMsg::ExecuteAction { id: 3 };
+        execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        let msg = QueryMsg::GetDenomFees {
+            denom: "earth".into(),
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: DenomFeesResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::new(5), value.pending.unwrap().value.min_fee);
+    }
+
+    #[test]
+    fn extend_rental() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::ExtendRental {
+            rental_id: "1".into(),
+            additional_duration: 20,
+        };
+        let info = mock_info("renter", &coins(100, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::InsufficientFunds {} => {}
+            _ => panic!("Must return InsufficientFunds error"),
+        }
+
+        let info = mock_info("renter", &coins(200, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap();
+        assert!(res.messages.is_empty());
+
+        let query_msg = QueryMsg::GetRental {
+            rental_id: "1".into(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(env.block.time.seconds() + 50, value.rental.end_time);
+        assert_eq!(coin(294 + 196, "earth"), value.rental.escrow);
+
+        // the seller turns renewals off
+        let set_msg = ExecuteMsg::SetRenewals {
+            offering_id: "1".into(),
+            enabled: false,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("renter", &[]),
+            set_msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return Unauthorized error"),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), set_msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let info = mock_info("renter", &coins(200, "earth"));
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        match err {
+            ContractError::RenewalsDisabled {} => {}
+            _ => panic!("Must return RenewalsDisabled error"),
+        }
+    }
+
+    #[test]
+    fn early_termination() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+
+        let msg = ExecuteMsg::SetEarlyTerminationPenalty {
+            offering_id: "1".into(),
+            penalty: Decimal::percent(101),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidPenalty {} => {}
+            _ => panic!("Must return InvalidPenalty error"),
+        }
+        let msg = ExecuteMsg::SetEarlyTerminationPenalty {
+            offering_id: "1".into(),
+            penalty: Decimal::percent(10),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // raising the penalty doesn't reach rentals already booked
+        let msg = ExecuteMsg::SetEarlyTerminationPenalty {
+            offering_id: "1".into(),
+            penalty: Decimal::one(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        // a third of the 294 escrow is used, 10% of the rest is kept as penalty
+        env.block.time = env.block.time.plus_seconds(10);
+        return_rental(deps.as_mut(), env.clone(), "renter", "1");
+        let msg = ExecuteMsg::EndRental {
+            rental_id: "1".into(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("renter", &[]), msg).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(98 + 19, "earth")
+            })
+        );
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(BankMsg::Send {
+                to_address: "renter".into(),
+                amount: coins(177, "earth")
+            })
+        );
+    }
+
+    #[test]
+    fn streaming_rent() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+
+        // 205 prepays 20 of the 100 seconds, and the odd 5 go back
+        let msg = ExecuteMsg::StreamRent {
+            offering_id: "1".into(),
+            duration: 100,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(205, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.messages.contains(&SubMsg::new(BankMsg::Send {
+            to_address: "renter".into(),
+            amount: coins(5, "earth")
+        })));
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let query_msg = QueryMsg::GetRental {
+            rental_id: "1".into(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(
+            mock_env().block.time.seconds() + 20,
+            value.rental.paid_until
+        );
+        assert_eq!(coin(196, "earth"), value.rental.escrow);
+        assert_eq!(coin(98, "earth"), value.rental.claimable);
+
+        let msg = ExecuteMsg::ClaimRent {
+            rental_id: "1".into(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(98, "earth")
+            })]
+        );
+
+        let clawback = ExecuteMsg::Clawback {
+            rental_id: "1".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            clawback.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::RentalNotExpired {} => {}
+            _ => panic!("Must return RentalNotExpired error"),
+        }
+
+        // topping up prepays another 10 seconds
+        let top_up = ExecuteMsg::TopUpRent {
+            rental_id: "1".into(),
+        };
+        let info = mock_info("renter", &coins(100, "earth"));
+        execute(deps.as_mut(), env.clone(), info, top_up).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NothingToClaim {} => {}
+            _ => panic!("Must return NothingToClaim error"),
+        }
+
+        // once the balance runs out the seller can claw back with the rest
+        env.block.time = env.block.time.plus_seconds(20);
+        return_rental(deps.as_mut(), env.clone(), "renter", "1");
+        let res = execute(deps.as_mut(), env, mock_info("owner", &[]), clawback).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(196, "earth")
+            })
+        );
+    }
+
+    #[test]
+    fn rental_terms() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let sell_msg = SellRwa {
+            list_price: coin(1000, "earth"),
+            listing_type: Some(ListingType::Rent),
+            rental_terms: Some(RentalTerms {
+                rate: coin(1, "earth"),
+                period: RentPeriod::Day,
+                min_duration: 10,
+                max_duration: Some(100),
+            }),
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("rwa-token", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        match err {
+            ContractError::NotForSale {} => {}
+            _ => panic!("Must return NotForSale error"),
+        }
+
+        for duration in [5, 101] {
+            let msg = ExecuteMsg::RentRwa {
+                offering_id: "1".into(),
+                duration,
+                referrer: None,
+                max_total: None,
+            };
+            let info = mock_info("renter", &coins(1, "earth"));
+            let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+            match err {
+                ContractError::InvalidDuration {} => {}
+                _ => panic!("Must return InvalidDuration error"),
+            }
+        }
+
+        // part of a day at a daily rate is rounded up
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 100,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(1, "earth"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn reservations() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+        let now = env.block.time.seconds();
+
+        let reserve = |start_time: u64, duration: u64| ExecuteMsg::ReserveRental {
+            offering_id: "1".into(),
+            start_time,
+            duration,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        for start in [now, now + MAX_RESERVATION_LEAD + 1] {
+            let err =
+                execute(deps.as_mut(), env.clone(), info.clone(), reserve(start, 30)).unwrap_err();
+            match err {
+                ContractError::InvalidStartTime {} => {}
+                _ => panic!("Must return InvalidStartTime error"),
+            }
+        }
+
+        // the RWA stays in the contract until the reservation starts
+        let res = execute(deps.as_mut(), env.clone(), info, reserve(now + 100, 30)).unwrap();
+        assert!(res.messages.is_empty());
+
+        for (start, duration) in [(now + 80, 30), (now + 120, 30), (now + 100, 10)] {
+            let info = mock_info("other", &coins(300, "earth"));
+            let err =
+                execute(deps.as_mut(), env.clone(), info, reserve(start, duration)).unwrap_err();
+            match err {
+                ContractError::WindowBooked {} => {}
+                _ => panic!("Must return WindowBooked error"),
+            }
+        }
+        let info = mock_info("other", &coins(300, "earth"));
+        execute(deps.as_mut(), env.clone(), info, reserve(now + 130, 30)).unwrap();
+
+        // a rental can't be extended into the next booking
+        let msg = ExecuteMsg::ExtendRental {
+            rental_id: "1".into(),
+            additional_duration: 10,
+        };
+        let info = mock_info("renter", &coins(100, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match err {
+            ContractError::WindowBooked {} => {}
+            _ => panic!("Must return WindowBooked error"),
+        }
+
+        let msg = QueryMsg::Bookings {
+            offering_id: "1".into(),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: BookingsResponse = from_json(&res).unwrap();
+        assert_eq!(
+            value.bookings,
+            vec![
+                Booking {
+                    rental_id: "1".into(),
+                    renter: Addr::unchecked("renter"),
+                    start_time: now + 100,
+                    end_time: now + 130,
+                },
+                Booking {
+                    rental_id: "2".into(),
+                    renter: Addr::unchecked("other"),
+                    start_time: now + 130,
+                    end_time: now + 160,
+                },
+            ]
+        );
+
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(10, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match err {
+            ContractError::OfferingBooked {} => {}
+            _ => panic!("Must return OfferingBooked error"),
+        }
+
+        let activate = ExecuteMsg::ActivateRental {
+            rental_id: "1".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            activate.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::RentalNotStarted {} => {}
+            _ => panic!("Must return RentalNotStarted error"),
+        }
+
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            activate,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "rwa-token".into(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: "renter".into(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        let withdraw = |offering_id: &str| ExecuteMsg::WithdrawRwa {
+            offering_id: offering_id.into(),
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), env.clone(), owner.clone(), withdraw("1")).unwrap_err();
+        match err {
+            ContractError::OfferingBooked {} => {}
+            _ => panic!("Must return OfferingBooked error"),
+        }
+
+        // withdrawing cancels reservations that haven't started, fee included
+        list_rwa(deps.as_mut(), 10);
+        let msg = ExecuteMsg::ReserveRental {
+            offering_id: "2".into(),
+            start_time: now + 1000,
+            duration: 30,
+            max_total: None,
+        };
+        let info = mock_info("other", &coins(300, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), owner, withdraw("2")).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: "other".into(),
+                amount: coins(300, "earth"),
+            })
+        );
+        let msg = QueryMsg::CollectedFees {
+            denom: "earth".into(),
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: CollectedFeesResponse = from_json(&res).unwrap();
+        assert_eq!(coin(6, "earth"), value.collected);
+    }
+
+    #[test]
+    fn sublet() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 100,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(1000, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let sublet_msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "renter".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sublet(SubletRwa {
+                rental_id: "1".into(),
+                rental_terms: RentalTerms {
+                    rate: coin(20, "earth"),
+                    period: RentPeriod::Second,
+                    min_duration: 1,
+                    max_duration: None,
+                },
+            }))
+            .unwrap(),
+        });
+        let info = mock_info("rwa-token", &[]);
+        let err =
+            execute(deps.as_mut(), env.clone(), info.clone(), sublet_msg.clone()).unwrap_err();
+        match err {
+            ContractError::SubletNotAllowed {} => {}
+            _ => panic!("Must return SubletNotAllowed error"),
+        }
+
+        let msg = ExecuteMsg::SetSubletPolicy {
+            offering_id: "1".into(),
+            allow_sublet: true,
+            lessor_cut: Decimal::percent(20),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        execute(deps.as_mut(), env.clone(), info, sublet_msg).unwrap();
+
+        // the sublet can't outlast the parent rental
+        let sub_rent = |duration: u64| ExecuteMsg::RentRwa {
+            offering_id: "2".into(),
+            duration,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("subtenant", &coins(4000, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, sub_rent(200)).unwrap_err();
+        match err {
+            ContractError::InvalidDuration {} => {}
+            _ => panic!("Must return InvalidDuration error"),
+        }
+        let info = mock_info("subtenant", &coins(1000, "earth"));
+        execute(deps.as_mut(), env.clone(), info, sub_rent(50)).unwrap();
+
+        let end_parent = ExecuteMsg::EndRental {
+            rental_id: "1".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("renter", &[]),
+            end_parent,
+        )
+        .unwrap_err();
+        match err {
+            ContractError::OfferingBooked {} => {}
+            _ => panic!("Must return OfferingBooked error"),
+        }
+
+        // the returned RWA stays listed for sublet and the lessor takes 20% of the rent
+        env.block.time = env.block.time.plus_seconds(50);
+        return_rental(deps.as_mut(), env.clone(), "subtenant", "2");
+        let msg = ExecuteMsg::EndRental {
+            rental_id: "2".into(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("subtenant", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(196, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "renter".into(),
+                    amount: coins(784, "earth")
+                }),
+            ]
+        );
+        assert!(OFFERINGS.has(&deps.storage, "2"));
+
+        // clawing back the parent settles the open sublet too, once the subtenant returns the RWA
+        let info = mock_info("subtenant", &coins(800, "earth"));
+        execute(deps.as_mut(), env.clone(), info, sub_rent(40)).unwrap();
+        env.block.time = env.block.time.plus_seconds(50);
+        let msg = ExecuteMsg::Clawback {
+            rental_id: "1".into(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("settled", "false")));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(980, "earth")
+            })]
+        );
+        return_rental(deps.as_mut(), env.clone(), "subtenant", "3");
+        let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(BankMsg::Send {
+                to_address: "renter".into(),
+                amount: coins(628, "earth")
+            })
+        );
+        let msg = QueryMsg::GetRental {
+            rental_id: "3".into(),
+        };
+        query(deps.as_ref(), env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn rent_to_own() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+
+        let sell_msg = SellRwa {
+            list_price: coin(1000, "earth"),
+            listing_type: None,
+            rental_terms: Some(RentalTerms {
+                rate: coin(10, "earth"),
+                period: RentPeriod::Second,
+                min_duration: 1,
+                max_duration: None,
+            }),
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("rwa-token", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetRentToOwn {
+            offering_id: "1".into(),
+            equity_rate: Decimal::percent(150),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidRentToOwn {} => {}
+            _ => panic!("Must return InvalidRentToOwn error"),
+        }
+        let msg = ExecuteMsg::SetRentToOwn {
+            offering_id: "1".into(),
+            equity_rate: Decimal::percent(50),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        // half of each payment goes toward the 1000 price
+        let msg = ExecuteMsg::StreamRent {
+            offering_id: "1".into(),
+            duration: 1000,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(1000, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.attributes.contains(&attr("purchased", "false")));
+
+        let query_msg = QueryMsg::GetRental {
+            rental_id: "1".into(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(coin(500, "earth"), value.rental.equity);
+        assert_eq!(coin(490, "earth"), value.rental.escrow);
+
+        // turning rent-to-own off doesn't touch the running agreement
+        let msg = ExecuteMsg::SetRentToOwn {
+            offering_id: "1".into(),
+            equity_rate: Decimal::zero(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        // the payment that covers the price buys the lot through the sale path
+        env.block.time = env.block.time.plus_seconds(50);
+        let msg = ExecuteMsg::TopUpRent {
+            rental_id: "1".into(),
+        };
+        let info = mock_info("renter", &coins(1000, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.attributes.contains(&attr("purchased", "true")));
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(980, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(245, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "renter".into(),
+                    amount: coins(735, "earth")
+                }),
+            ]
+        );
+
+        query(deps.as_ref(), env.clone(), query_msg).unwrap_err();
+        let msg = QueryMsg::AllOffers {
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: OffersResponse = from_json(&res).unwrap();
+        assert!(value.offers.is_empty());
+
+        // a lease whose equity covers the price while buying is paused
+        let sell_msg = SellRwa {
+            list_price: coin(400, "earth"),
+            listing_type: None,
+            rental_terms: Some(RentalTerms {
+                rate: coin(10, "earth"),
+                period: RentPeriod::Second,
+                min_duration: 1,
+                max_duration: None,
+            }),
+            allowed_buyers: None,
+        };
+        let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "owner".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("rwa-token", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetRentToOwn {
+            offering_id: "2".into(),
+            equity_rate: Decimal::percent(50),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        let pause = ExecuteMsg::Pause {
+            operations: Some(vec![Operation::Buy]),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), pause).unwrap();
+        let lease = |renter: &str| {
+            let msg = ExecuteMsg::RentRwa {
+                offering_id: "2".into(),
+                duration: 100,
+                referrer: None,
+                max_total: None,
+            };
+            (mock_info(renter, &coins(1000, "earth")), msg)
+        };
+        let (info, msg) = lease("renter");
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.attributes.contains(&attr("purchased", "false")));
+
+        // nothing else can be booked while the lease runs
+        let msg = ExecuteMsg::ReserveRental {
+            offering_id: "2".into(),
+            start_time: env.block.time.seconds() + 200,
+            duration: 1,
+            max_total: None,
+        };
+        let info = mock_info("friend", &coins(10, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match err {
+            ContractError::WindowBooked {} => {}
+            _ => panic!("Must return WindowBooked error"),
+        }
+
+        let complete = |rental_id: &str| ExecuteMsg::CompletePurchase {
+            rental_id: rental_id.into(),
+        };
+        let anyone = mock_info("anyone", &[]);
+        let err = execute(deps.as_mut(), env.clone(), anyone.clone(), complete("2")).unwrap_err();
+        match err {
+            ContractError::PurchaseBlocked {} => {}
+            _ => panic!("Must return PurchaseBlocked error"),
+        }
+
+        // equity that covered the price goes back if the purchase never completed
+        env.block.time = env.block.time.plus_seconds(100);
+        return_rental(deps.as_mut(), env.clone(), "renter", "2");
+        let msg = ExecuteMsg::EndRental {
+            rental_id: "2".into(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("renter", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(490, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "renter".into(),
+                    amount: coins(500, "earth")
+                }),
+            ]
+        );
+
+        // once buying resumes, anyone can complete the next lease's purchase
+        let (info, msg) = lease("other");
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let unpause = ExecuteMsg::Unpause {
+            operations: Some(vec![Operation::Buy]),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            unpause,
+        )
+        .unwrap();
+        let res = execute(deps.as_mut(), env.clone(), anyone, complete("3")).unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("action", "complete_purchase")));
+        let msg = QueryMsg::AllOffers {
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: OffersResponse = from_json(&res).unwrap();
+        assert!(value.offers.is_empty());
+    }
+
+    #[test]
+    fn late_fees() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+
+        let msg = ExecuteMsg::SetLateFees {
+            offering_id: "1".into(),
+            grace_period: 10,
+            late_fee_rate: Uint128::new(5),
+            security_deposit: Uint128::zero(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // the terms are fixed once booked, even an endless grace period can't be added later
+        let msg = ExecuteMsg::SetLateFees {
+            offering_id: "1".into(),
+            grace_period: u64::MAX,
+            late_fee_rate: Uint128::new(5),
+            security_deposit: Uint128::zero(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        // nothing is owed within the grace period
+        let query_msg = QueryMsg::GetRental {
+            rental_id: "1".into(),
+        };
+        env.block.time = env.block.time.plus_seconds(40);
+        let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(coin(0, "earth"), value.rental.amount_owed);
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(coin(50, "earth"), value.rental.amount_owed);
+
+        // overlapping contract and asset pauses from +50 to +90 don't count
+        let pauses = [
+            (
+                0,
+                ExecuteMsg::Pause {
+                    operations: Some(vec![Operation::RentalSettlement]),
+                },
+            ),
+            (
+                20,
+                ExecuteMsg::PauseAsset {
+                    contract: "rwa-token".into(),
+                },
+            ),
+            (
+                10,
+                ExecuteMsg::Unpause {
+                    operations: Some(vec![Operation::RentalSettlement]),
+                },
+            ),
+            (
+                10,
+                ExecuteMsg::UnpauseAsset {
+                    contract: "rwa-token".into(),
+                },
+            ),
+        ];
+        for (wait, msg) in pauses {
+            env.block.time = env.block.time.plus_seconds(wait);
+            execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        }
+        env.block.time = env.block.time.plus_seconds(10);
+        let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(coin(100, "earth"), value.rental.amount_owed);
+
+        // and nothing more accrues once the RWA is back
+        return_rental(deps.as_mut(), env.clone(), "renter", "1");
+        env.block.time = env.block.time.plus_seconds(50);
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(coin(100, "earth"), value.rental.amount_owed);
+        let msg = ExecuteMsg::EndRental {
+            rental_id: "1".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("renter", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::InsufficientFunds {} => {}
+            _ => panic!("Must return InsufficientFunds error"),
+        }
+
+        // overpaying the late fee is refunded
+        let info = mock_info("renter", &coins(110, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(294 + 100, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "renter".into(),
+                    amount: coins(10, "earth")
+                }),
+            ]
+        );
+
+        // the next rental gets the endless grace period, which never overflows
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(1000);
+        let query_msg = QueryMsg::GetRental {
+            rental_id: "2".into(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let value: RentalResponse = from_json(&res).unwrap();
+        assert_eq!(coin(0, "earth"), value.rental.amount_owed);
+        return_rental(deps.as_mut(), env.clone(), "renter", "2");
+        let msg = ExecuteMsg::EndRental {
+            rental_id: "2".into(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("renter", &[]), msg).unwrap();
+
+        // a deposit on top of the rent covers late fees on clawback
+        let msg = ExecuteMsg::SetLateFees {
+            offering_id: "1".into(),
+            grace_period: 0,
+            late_fee_rate: Uint128::new(5),
+            security_deposit: Uint128::new(100),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::InsufficientFunds {} => {}
+            _ => panic!("Must return InsufficientFunds error"),
+        }
+        let info = mock_info("renter", &coins(400, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // keeping the RWA doesn't protect the deposit
+        env.block.time = env.block.time.plus_seconds(40);
+        let msg = ExecuteMsg::Clawback {
+            rental_id: "3".into(),
+        };
+        let owner = mock_info("owner", &[]);
+        let res = execute(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap();
+        assert!(res.attributes.contains(&attr("settled", "false")));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(294 + 50, "earth")
+            })]
+        );
+
+        // the rest of the deposit goes once the late fee reaches it
+        env.block.time = env.block.time.plus_seconds(30);
+        return_rental(deps.as_mut(), env.clone(), "renter", "3");
+        let res = execute(deps.as_mut(), env, owner, msg).unwrap();
+        assert!(res.attributes.contains(&attr("settled", "true")));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(50, "earth")
+            })]
+        );
     }
-}
 
-fn query_count(deps: Deps) -> StdResult<CountResponse> {
-    let state = STATE.load(deps.storage)?;
-    Ok(CountResponse {
-        count: state.num_offerings,
-    })
-}
+    #[test]
+    fn expired_reservation_is_refunded() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+        let now = env.block.time.seconds();
 
-fn query_fee(deps: Deps) -> StdResult<FeeResponse> {
-    let state = STATE.load(deps.storage)?;
-    Ok(FeeResponse { fee: state.fee })
-}
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = ExecuteMsg::ReserveRental {
+            offering_id: "1".into(),
+            start_time: now + 35,
+            duration: 5,
+            max_total: None,
+        };
+        let info = mock_info("other", &coins(50, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-fn query_all(
-    deps: Deps,
-    start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<OffersResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+        // the holdover kept the reservation from ever starting, so it's refunded in full
+        env.block.time = env.block.time.plus_seconds(45);
+        let msg = QueryMsg::ExpiredRentals {
+            start_after: None,
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: ExpiredRentalsResponse = from_json(&res).unwrap();
+        assert_eq!(0, value.count);
+        let cursor = value.last_scanned.unwrap();
+        assert_eq!("1", cursor.rental_id);
 
-    let offers: StdResult<Vec<Offer>> = OFFERINGS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| item.map(map_offer))
-        .collect();
+        let msg = ExecuteMsg::ProcessExpiredRentals {
+            start_after: Some(cursor),
+            limit: Some(1),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap();
+        assert!(res.attributes.contains(&attr("settled", "1")));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "other".into(),
+                amount: coins(50, "earth")
+            })]
+        );
+        let msg = QueryMsg::GetRental {
+            rental_id: "2".into(),
+        };
+        query(deps.as_ref(), env, msg).unwrap_err();
+    }
 
-    Ok(OffersResponse { offers: offers? })
-}
+    #[test]
+    fn process_expired_rentals() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+        list_rwa(deps.as_mut(), 10);
 
-fn query_rental(deps: Deps, rental_id: String) -> StdResult<RentalResponse> {
-    let rental = RENTALS.load(deps.storage, &rental_id)?;
-    let off = OFFERINGS.load(deps.storage, &rental.offering_id)?;
-    Ok(RentalResponse {
-        rental: RentalInfo {
-            id: rental.id,
-            offering_id: rental.offering_id,
-            renter: rental.renter,
-            start_time: rental.start_time,
-            end_time: rental.end_time,
-            amount: off.amount,
-        },
-    })
-}
+        let msg = ExecuteMsg::SetKeeperBounty {
+            bounty: Some(coin(10, "earth")),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
 
-fn map_offer((k, v): (String, Offering)) -> Offer {
-    Offer {
-        id: k,
-        amount: v.amount,
-        contract: v.contract,
-        seller: v.seller,
-        list_price: v.list_price,
-    }
-}
+        for (offering_id, duration) in [("1", 30), ("2", 60)] {
+            let msg = ExecuteMsg::RentRwa {
+                offering_id: offering_id.into(),
+                duration,
+                referrer: None,
+                max_total: None,
+            };
+            let info = mock_info("renter", &coins(600, "earth"));
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
 
-pub fn increment_rentals(store: &mut dyn Storage) -> StdResult<u64> {
-    let id: u64 = RENTALS
-        .keys(store, None, None, Order::Descending)
-        .next()
-        .map(|r| r.and_then(|id| id.parse::<u64>().map_err(StdError::invalid_utf8)))
-        .transpose()?
-        .unwrap_or(0)
-        + 1;
-    Ok(id)
-}
+        env.block.time = env.block.time.plus_seconds(40);
+        let expired = QueryMsg::ExpiredRentals {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), expired.clone()).unwrap();
+        let value: ExpiredRentalsResponse = from_json(&res).unwrap();
+        assert_eq!(0, value.count);
+        assert_eq!(None, value.last_scanned);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{
-        mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
-    };
-    use cosmwasm_std::{attr, coins, Decimal, SubMsg};
-    use cw20::Cw20ReceiveMsg;
+        // rentals whose RWA is still out are left alone
+        let msg = ExecuteMsg::ProcessExpiredRentals {
+            start_after: None,
+            limit: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("settled", "0")));
+        assert!(res.messages.is_empty());
 
-    fn setup(deps: DepsMut) {
-        let msg = InstantiateMsg {
-            fee: Decimal::percent(2),
+        // the bounty is capped at the 6 fee the rental paid
+        return_rental(deps.as_mut(), env.clone(), "renter", "1");
+        let res = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap();
+        assert!(res.attributes.contains(&attr("settled", "1")));
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(294, "earth")
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "keeper".into(),
+                    amount: coins(6, "earth")
+                }),
+            ]
+        );
+        let msg = QueryMsg::CollectedFees {
+            denom: "earth".into(),
         };
-        let info = mock_info("creator", &[]);
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: CollectedFeesResponse = from_json(&res).unwrap();
+        assert_eq!(coin(6 + 12 - 6, "earth"), value.collected);
 
-        // we can just call .unwrap() to assert this was a success
-        instantiate(deps, mock_env(), info, msg).unwrap();
+        let res = query(deps.as_ref(), env.clone(), expired).unwrap();
+        let value: ExpiredRentalsResponse = from_json(&res).unwrap();
+        assert_eq!(0, value.count);
+        let msg = QueryMsg::GetRental {
+            rental_id: "1".into(),
+        };
+        query(deps.as_ref(), env, msg).unwrap_err();
     }
 
     #[test]
-    fn proper_initialization() {
+    fn lending() {
         let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        register_asset(deps.as_mut());
 
-        let msg = InstantiateMsg {
-            fee: Decimal::percent(2),
+        let msg = ExecuteMsg::DepositLiquidity {};
+        let info = mock_info("lender", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // no terms yet
+        let open = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+            sender: "borrower".into(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveHookMsg::OpenLoan(OpenLoan {
+                denom: "earth".into(),
+            }))
+            .unwrap(),
+        });
+        let info = mock_info("rwa-token", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), open.clone()).unwrap_err();
+        match err {
+            ContractError::LendingDisabled {} => {}
+            _ => panic!("Must return lending disabled error"),
+        }
+
+        let msg = ExecuteMsg::SetAssetValuation {
+            contract: "rwa-token".into(),
+            price: coin(10, "earth"),
         };
-        let info = mock_info("creator", &[]);
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let terms = LendingTerms {
+            max_ltv: Decimal::percent(50),
+            liquidation_ltv: Decimal::percent(80),
+            interest_rate: Decimal::zero(),
+            liquidation_discount: Decimal::percent(10),
+        };
+        let msg = ExecuteMsg::SetLendingTerms {
+            contract: "rwa-token".into(),
+            terms: Some(terms.clone()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), info, open.clone()).unwrap();
 
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::Borrow {
+            loan_id: 1,
+            amount: Uint128::new(600),
+        };
+        let info = mock_info("borrower", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::ExceedsLtv {} => {}
+            _ => panic!("Must return exceeds ltv error"),
+        }
+        let msg = ExecuteMsg::Borrow {
+            loan_id: 1,
+            amount: Uint128::new(500),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Liquidate { loan_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg).unwrap_err();
+        match err {
+            ContractError::LoanHealthy {} => {}
+            _ => panic!("Must return loan healthy error"),
+        }
+
+        // price drop pushes the loan past the liquidation threshold
+        let msg = ExecuteMsg::SetAssetValuation {
+            contract: "rwa-token".into(),
+            price: coin(6, "earth"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // turning lending off doesn't shield open loans from liquidation
+        let msg = ExecuteMsg::SetLendingTerms {
+            contract: "rwa-token".into(),
+            terms: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetLoan { loan_id: 1 }).unwrap();
+        let value: LoanResponse = from_json(&res).unwrap();
+        assert!(value.liquidatable);
+        assert_eq!(value.terms.liquidation_ltv, Decimal::percent(80));
+        assert_eq!(value.collateral_value, coin(600, "earth"));
+
+        let msg = ExecuteMsg::Liquidate { loan_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::WithdrawRwa {
+            offering_id: "1".into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("borrower", &[]), msg).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // listed 10% under the 600 value, 540 less the 2% fee repays the 500 debt and the
+        // rest goes to the borrower
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(540, "earth"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: "borrower".into(),
+                amount: coins(30, "earth"),
+            })
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetPool {
+                denom: "earth".into(),
+            },
+        )
+        .unwrap();
+        let value: PoolResponse = from_json(&res).unwrap();
+        assert_eq!(value.liquidity, coin(1000, "earth"));
+        assert_eq!(value.borrowed, coin(0, "earth"));
+        query(deps.as_ref(), mock_env(), QueryMsg::GetLoan { loan_id: 1 }).unwrap_err();
+
+        // a liquidated loan repaid in full pulls the forced listing and frees the collateral
+        let msg = ExecuteMsg::SetLendingTerms {
+            contract: "rwa-token".into(),
+            terms: Some(terms),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("rwa-token", &[]), open).unwrap();
+        let msg = ExecuteMsg::Borrow {
+            loan_id: 2,
+            amount: Uint128::new(300),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("borrower", &[]), msg).unwrap();
+        let liquidate = ExecuteMsg::Liquidate { loan_id: 2 };
+
+        // a valuation in another denom says nothing about the loan
+        let msg = ExecuteMsg::SetAssetValuation {
+            contract: "rwa-token".into(),
+            price: coin(1, "mars"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetLoan { loan_id: 2 }).unwrap();
+        let value: LoanResponse = from_json(&res).unwrap();
+        assert!(!value.liquidatable);
+        let info = mock_info("keeper", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), liquidate.clone()).unwrap_err();
+        match err {
+            ContractError::NoValuation {} => {}
+            _ => panic!("Must return no valuation error"),
+        }
+
+        // 10% under the 300 value would leave the pool short, so it lists at the debt
+        let msg = ExecuteMsg::SetAssetValuation {
+            contract: "rwa-token".into(),
+            price: coin(3, "earth"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info, liquidate).unwrap();
+        assert!(res.attributes.contains(&attr("list_price", "300earth")));
+
+        let repay = ExecuteMsg::Repay { loan_id: 2 };
+        let info = mock_info("borrower", &coins(100, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info, repay.clone()).unwrap_err();
+        match err {
+            ContractError::LoanLiquidated {} => {}
+            _ => panic!("Must return loan liquidated error"),
+        }
+        let info = mock_info("borrower", &coins(300, "earth"));
+        let res = execute(deps.as_mut(), mock_env(), info, repay).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert!(!OFFERINGS.has(&deps.storage, "2"));
+        query(deps.as_ref(), mock_env(), QueryMsg::GetLoan { loan_id: 2 }).unwrap_err();
+    }
+
+    #[test]
+    fn installments() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 1000);
+        list_rwa(deps.as_mut(), 1000);
+
+        for offering_id in ["1", "2"] {
+            let msg = ExecuteMsg::SetInstallmentTerms {
+                offering_id: offering_id.into(),
+                terms: Some(InstallmentTerms {
+                    down_payment: Decimal::percent(20),
+                    installments: 4,
+                    interval: 100,
+                    forfeit_rate: Decimal::percent(50),
+                }),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::BuyInstallments {
+            offering_id: "1".into(),
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("buyer", &coins(100, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::InsufficientFunds {} => {}
+            _ => panic!("Must return insufficient funds error"),
+        }
+        let info = mock_info("buyer", &coins(200, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
+
+        let msg = ExecuteMsg::Pause {
+            operations: Some(vec![Operation::Buy]),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::PayInstallment {
+            offering_id: "1".into(),
+        };
+        let info = mock_info("buyer", &coins(200, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::Paused {} => {}
+            _ => panic!("Must return paused error"),
+        }
+
+        // the schedule stands still while payments can't be made
+        env.block.time = env.block.time.plus_seconds(250);
+        let unpause = ExecuteMsg::Unpause { operations: None };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            unpause,
+        )
+        .unwrap();
+        let plan = QueryMsg::GetInstallmentPlan {
+            offering_id: "1".into(),
+        };
+        let res = query(deps.as_ref(), env.clone(), plan.clone()).unwrap();
+        let value: InstallmentPlanResponse = from_json(&res).unwrap();
+        assert!(!value.defaulted);
+        assert_eq!(value.next_due, mock_env().block.time.seconds() + 100 + 250);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::ReclaimInstallments {
+            offering_id: "1".into(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InstallmentsCurrent {} => {}
+            _ => panic!("Must return installments current error"),
+        }
+
+        // the second installment is missed
+        env.block.time = env.block.time.plus_seconds(250);
+        let res = query(deps.as_ref(), env.clone(), plan).unwrap();
+        let value: InstallmentPlanResponse = from_json(&res).unwrap();
+        assert_eq!(value.paid, coin(400, "earth"));
+        assert_eq!(value.next_payment, coin(200, "earth"));
+        assert!(value.defaulted);
+
+        let msg = ExecuteMsg::ReclaimInstallments {
+            offering_id: "1".into(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[..2],
+            [
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(200, "earth"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "buyer".into(),
+                    amount: coins(200, "earth"),
+                }),
+            ]
+        );
+
+        // paying off the rest settles the sale and releases the RWA
+        let msg = ExecuteMsg::BuyInstallments {
+            offering_id: "2".into(),
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("buyer", &coins(200, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = ExecuteMsg::PayInstallment {
+            offering_id: "2".into(),
+        };
+        let info = mock_info("buyer", &coins(900, "earth"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[..2],
+            [
+                SubMsg::new(BankMsg::Send {
+                    to_address: "buyer".into(),
+                    amount: coins(100, "earth"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(980, "earth"),
+                }),
+            ]
+        );
+        assert_eq!(
+            res.messages.last().unwrap(),
+            &SubMsg::new(WasmMsg::Execute {
+                contract_addr: "rwa-token".into(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: "buyer".into(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let msg = QueryMsg::GetInstallmentPlan {
+            offering_id: "2".into(),
+        };
+        query(deps.as_ref(), env, msg).unwrap_err();
     }
 
     #[test]
-    fn sell_rwa() {
+    fn redemption_window() {
         let mut deps = mock_dependencies();
+        let mut env = mock_env();
         setup(deps.as_mut());
+        register_asset(deps.as_mut());
 
-        let sell_msg = SellRwa {
-            list_price: coin(1000, "earth"),
+        let closes_at = env.block.time.seconds() + 100;
+        let msg = ExecuteMsg::OpenRedemptionWindow {
+            contract: "rwa-token".into(),
+            price: coin(10, "earth"),
+            cap: Uint128::new(100),
+            closes_at,
+        };
+        let info = mock_info("issuer", &coins(999, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::InsufficientFunds {} => {}
+            _ => panic!("Must return insufficient funds error"),
+        }
+        let info = mock_info("issuer", &coins(1000, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // 200 tokens for a cap of 100 fill half of each request
+        let redeem = |holder: &str, amount: u128| {
+            ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+                sender: holder.into(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(&ReceiveHookMsg::Redeem(Redeem { window_id: 1 })).unwrap(),
+            })
+        };
+        let msg = ExecuteMsg::Pause {
+            operations: Some(vec![Operation::Redemption]),
         };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let info = mock_info("rwa-token", &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, redeem("alice", 150)).unwrap_err();
+        match err {
+            ContractError::Paused {} => {}
+            _ => panic!("Must return paused error"),
+        }
+        let msg = ExecuteMsg::Unpause { operations: None };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        for (holder, amount) in [("alice", 150), ("bob", 50)] {
+            let info = mock_info("rwa-token", &[]);
+            execute(deps.as_mut(), env.clone(), info, redeem(holder, amount)).unwrap();
+        }
+
+        let msg = ExecuteMsg::ClaimRedemption { window_id: 1 };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap_err();
+        match err {
+            ContractError::RedemptionOpen {} => {}
+            _ => panic!("Must return redemption open error"),
+        }
 
+        env.block.time = env.block.time.plus_seconds(100);
         let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
-            sender: "owner".into(),
-            amount: Uint128::new(100),
-            msg: to_json_binary(&sell_msg).unwrap(),
+            sender: "carol".into(),
+            amount: Uint128::new(10),
+            msg: to_json_binary(&ReceiveHookMsg::Redeem(Redeem { window_id: 1 })).unwrap(),
         });
-        let info = mock_info("rwa-token", &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("rwa-token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::RedemptionClosed {} => {}
+            _ => panic!("Must return redemption closed error"),
+        }
 
+        // fully subscribed, so nothing goes back to the issuer
+        let msg = ExecuteMsg::CloseRedemptionWindow { window_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
         assert_eq!(0, res.messages.len());
 
-        let msg = QueryMsg::AllOffers {
-            start_after: None,
-            limit: None,
+        let msg = ExecuteMsg::ClaimRedemption { window_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        let transfer = |recipient: &str, amount: u128| {
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "rwa-token".into(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.into(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
         };
-        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
-        let value: OffersResponse = from_json(&res).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "alice".into(),
+                    amount: coins(750, "earth"),
+                }),
+                transfer("issuer", 75),
+                transfer("alice", 75),
+            ]
+        );
 
-        assert_eq!(Uint128::new(100), value.offers.first().unwrap().amount);
+        let msg = ExecuteMsg::ClaimRedemption { window_id: 1 };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NothingToClaim {} => {}
+            _ => panic!("Must return nothing to claim error"),
+        }
+
+        // 15 tokens for a cap of 10 fill 3 of each 5, and the last claim returns the
+        // funding for the 10th token
+        let msg = ExecuteMsg::OpenRedemptionWindow {
+            contract: "rwa-token".into(),
+            price: coin(10, "earth"),
+            cap: Uint128::new(10),
+            closes_at: env.block.time.seconds() + 100,
+        };
+        let info = mock_info("issuer", &coins(100, "earth"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        for holder in ["alice", "bob", "carol"] {
+            let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+                sender: holder.into(),
+                amount: Uint128::new(5),
+                msg: to_json_binary(&ReceiveHookMsg::Redeem(Redeem { window_id: 2 })).unwrap(),
+            });
+            execute(deps.as_mut(), env.clone(), mock_info("rwa-token", &[]), msg).unwrap();
+        }
+        env.block.time = env.block.time.plus_seconds(100);
+        let msg = ExecuteMsg::CloseRedemptionWindow { window_id: 2 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        for holder in ["alice", "bob", "carol"] {
+            let msg = ExecuteMsg::ClaimRedemption { window_id: 2 };
+            let res = execute(deps.as_mut(), env.clone(), mock_info(holder, &[]), msg).unwrap();
+            assert_eq!(
+                res.messages[0],
+                SubMsg::new(BankMsg::Send {
+                    to_address: holder.into(),
+                    amount: coins(30, "earth"),
+                })
+            );
+            let dust = res.messages.contains(&SubMsg::new(BankMsg::Send {
+                to_address: "issuer".into(),
+                amount: coins(10, "earth"),
+            }));
+            assert_eq!(dust, holder == "carol");
+        }
     }
 
     #[test]
-    fn buy_rwa() {
+    fn batch_operations() {
         let mut deps = mock_dependencies();
         setup(deps.as_mut());
 
-        let sell_msg = SellRwa {
-            list_price: coin(1000, "earth"),
+        let lot = |amount: u128, price: u128| SellLot {
+            amount: Uint128::new(amount),
+            listing: SellRwa {
+                list_price: coin(price, "earth"),
+                listing_type: None,
+                rental_terms: None,
+                allowed_buyers: None,
+            },
         };
-
+        let sell_batch = |amount: u128| {
+            ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+                sender: "owner".into(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(&ReceiveHookMsg::SellBatch(SellBatch {
+                    lots: vec![lot(30, 300), lot(30, 300), lot(40, 500)],
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info("rwa-token", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), sell_batch(90)).unwrap_err();
+        match err {
+            ContractError::InvalidBatch {} => {}
+            _ => panic!("Must return invalid batch error"),
+        }
+        // lots that overflow when added up are rejected, not a panic
         let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
             sender: "owner".into(),
-            amount: Uint128::new(100),
-            msg: to_json_binary(&sell_msg).unwrap(),
+            amount: Uint128::new(1),
+            msg: to_json_binary(&ReceiveHookMsg::SellBatch(SellBatch {
+                lots: vec![lot(u128::MAX, 300), lot(2, 300)],
+            }))
+            .unwrap(),
         });
-        let info = mock_info("rwa-token", &[]);
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::InvalidBatch {} => {}
+            _ => panic!("Must return invalid batch error"),
+        }
+        execute(deps.as_mut(), mock_env(), info, sell_batch(100)).unwrap();
 
-        // Now buy the RWA tokens
-        let msg = ExecuteMsg::Buy {
-            offering_id: "1".into(),
+        let msg = ExecuteMsg::BatchBuy {
+            offering_ids: vec!["1".into(), "2".into()],
+            max_total: coin(500, "earth"),
         };
-        let info = mock_info("buyer", &coins(1000, "earth"));
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("buyer", &coins(700, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::MaxTotalExceeded {} => {}
+            _ => panic!("Must return max total exceeded error"),
+        }
 
-        assert_eq!(2, res.messages.len());
-        assert_eq!(
-            res.messages[0],
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                to_address: "owner".into(),
-                amount: coins(980, "earth")
-            }))
-        );
+        // a missing offering fails the whole batch
+        let msg = ExecuteMsg::BatchBuy {
+            offering_ids: vec!["1".into(), "4".into()],
+            max_total: coin(600, "earth"),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+
+        // the same offering can't be bought twice in one batch
+        let msg = ExecuteMsg::BatchBuy {
+            offering_ids: vec!["1".into(), "1".into()],
+            max_total: coin(600, "earth"),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::InvalidBatch {} => {}
+            _ => panic!("Must return invalid batch error"),
+        }
+
+        let msg = ExecuteMsg::BatchBuy {
+            offering_ids: vec!["1".into(), "2".into()],
+            max_total: coin(600, "earth"),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(5, res.messages.len());
         assert_eq!(
-            res.messages[1],
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "rwa-token".into(),
-                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-                    recipient: "buyer".into(),
-                    amount: Uint128::new(100),
-                })
-                .unwrap(),
-                funds: vec![],
-            }))
+            res.messages[4],
+            SubMsg::new(BankMsg::Send {
+                to_address: "buyer".into(),
+                amount: coins(100, "earth"),
+            })
         );
 
-        // Check that the offering has been removed
+        let msg = ExecuteMsg::BatchWithdraw {
+            offering_ids: vec!["1".into(), "3".into()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        let msg = ExecuteMsg::BatchWithdraw {
+            offering_ids: vec!["3".into()],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
         let msg = QueryMsg::AllOffers {
             start_after: None,
             limit: None,
+            viewer: None,
         };
         let res = query(deps.as_ref(), mock_env(), msg).unwrap();
         let value: OffersResponse = from_json(&res).unwrap();
@@ -544,193 +7767,394 @@ mod tests {
     }
 
     #[test]
-    fn withdraw_fees() {
-        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+    fn slippage_limits() {
+        let mut deps = mock_dependencies();
         setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
 
-        let msg = ExecuteMsg::WithdrawFees {
-            amount: 1000u32.into(),
-            denom: "earth".into(),
+        let buy = |max_price: u128, expected_fee: u128| ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: Some(Uint128::new(max_price)),
+            expected_fee: Some(Uint128::new(expected_fee)),
         };
-        let info = mock_info("anyone", &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-        match res {
-            Err(ContractError::Unauthorized {}) => {}
-            _ => panic!("Must return Unauthorized error"),
+        let info = mock_info("buyer", &coins(10, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy(9, 1)).unwrap_err();
+        match err {
+            ContractError::MaxPriceExceeded {} => {}
+            _ => panic!("Must return max price exceeded error"),
+        }
+        let msg = ExecuteMsg::ChangeFee {
+            fee: Decimal::percent(10),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(86400);
+        let err = execute(deps.as_mut(), env.clone(), info, buy(10, 0)).unwrap_err();
+        match err {
+            ContractError::FeeExceeded {} => {}
+            _ => panic!("Must return fee exceeded error"),
         }
 
-        let info = mock_info("creator", &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(1, res.messages.len());
-        assert_eq!(
-            res.messages[0],
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                to_address: "creator".into(),
-                amount: coins(1000, "earth")
-            }))
-        );
+        // 30 seconds at 10 per second, plus the deposit
+        let msg = ExecuteMsg::SetLateFees {
+            offering_id: "1".into(),
+            grace_period: 0,
+            late_fee_rate: Uint128::zero(),
+            security_deposit: Uint128::new(50),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        let max_total = Some(Uint128::new(349));
+        let rent = |max_total: Option<Uint128>| ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total,
+        };
+        let stream = ExecuteMsg::StreamRent {
+            offering_id: "1".into(),
+            duration: 60,
+            referrer: None,
+            max_total,
+        };
+        let reserve = ExecuteMsg::ReserveRental {
+            offering_id: "1".into(),
+            start_time: env.block.time.seconds() + 100,
+            duration: 30,
+            max_total,
+        };
+        let info = mock_info("renter", &coins(350, "earth"));
+        for msg in [rent(max_total), stream, reserve] {
+            let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            match err {
+                ContractError::MaxTotalExceeded {} => {}
+                _ => panic!("Must return max total exceeded error"),
+            }
+        }
+        let msg = rent(Some(Uint128::new(350)));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // the whole price of an installment plan, not just the down payment
+        list_rwa(deps.as_mut(), 1000);
+        let msg = ExecuteMsg::SetInstallmentTerms {
+            offering_id: "2".into(),
+            terms: Some(InstallmentTerms {
+                down_payment: Decimal::percent(20),
+                installments: 4,
+                interval: 100,
+                forfeit_rate: Decimal::percent(50),
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        let buy = |max_total: u128| ExecuteMsg::BuyInstallments {
+            offering_id: "2".into(),
+            referrer: None,
+            max_total: Some(Uint128::new(max_total)),
+        };
+        let info = mock_info("buyer", &coins(200, "earth"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), buy(999)).unwrap_err();
+        match err {
+            ContractError::MaxTotalExceeded {} => {}
+            _ => panic!("Must return max total exceeded error"),
+        }
+        execute(deps.as_mut(), env, info, buy(1000)).unwrap();
     }
 
     #[test]
-    fn change_fee() {
+    fn simulate_queries() {
         let mut deps = mock_dependencies();
         setup(deps.as_mut());
+        list_rwa(deps.as_mut(), 10);
+        list_rwa(deps.as_mut(), 1000);
 
-        let msg = ExecuteMsg::ChangeFee {
-            fee: Decimal::percent(3),
+        let msg = QueryMsg::SimulateRent {
+            offering_id: "1".into(),
+            duration: 30,
+            renter: Some("renter".into()),
         };
-        let info = mock_info("anyone", &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-        match res {
-            Err(ContractError::Unauthorized {}) => {}
-            _ => panic!("Must return Unauthorized error"),
-        }
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let sim: SimulateRentResponse = from_json(&res).unwrap();
+        assert_eq!(sim.price, coin(300, "earth"));
+        assert_eq!(sim.seller_amount, coin(294, "earth"));
+        assert_eq!(sim.deposit, coin(0, "earth"));
 
-        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::RentRwa {
+            offering_id: "1".into(),
+            duration: 30,
+            referrer: None,
+            max_total: None,
+        };
+        let info = mock_info("renter", &coins(300, "earth"));
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        assert!(res
+            .attributes
+            .contains(&attr("protocol_fee", sim.protocol_fee.amount)));
 
-        let msg = QueryMsg::GetFee {};
+        // the window is now booked, and a booked offering can't be bought
+        let msg = QueryMsg::SimulateRent {
+            offering_id: "1".into(),
+            duration: 30,
+            renter: Some("other".into()),
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        let quote = |offering_id: &str, buyer: &str, amount: u128| QueryMsg::QuoteBuy {
+            offering_id: offering_id.into(),
+            buyer: Some(buyer.into()),
+            amount: Some(Uint128::new(amount)),
+        };
+        query(deps.as_ref(), mock_env(), quote("1", "buyer", 10)).unwrap_err();
+        query(deps.as_ref(), mock_env(), quote("2", "owner", 1000)).unwrap_err();
+        query(deps.as_ref(), mock_env(), quote("2", "buyer", 999)).unwrap_err();
+        let res = query(deps.as_ref(), mock_env(), quote("2", "buyer", 1100)).unwrap();
+        let sim: QuoteResponse = from_json(&res).unwrap();
+        assert_eq!(sim.protocol_fee, coin(20, "earth"));
+        assert_eq!(sim.refund, coin(100, "earth"));
+        let msg = QueryMsg::SimulateBuy {
+            offering_id: "2".into(),
+            amount: Uint128::new(1100),
+            buyer: Some("buyer".into()),
+        };
         let res = query(deps.as_ref(), mock_env(), msg).unwrap();
-        let value: FeeResponse = from_json(&res).unwrap();
-        assert_eq!(Decimal::percent(3), value.fee);
+        assert_eq!(sim, from_json::<QuoteResponse>(&res).unwrap());
+
+        let msg = ExecuteMsg::Buy {
+            offering_id: "2".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1100, "earth"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: vec![sim.seller_amount],
+            })
+        );
+        assert_eq!(
+            res.messages.last().unwrap(),
+            &SubMsg::new(BankMsg::Send {
+                to_address: "buyer".into(),
+                amount: vec![sim.refund],
+            })
+        );
     }
 
     #[test]
-    fn rent_rwa() {
+    fn private_listing() {
         let mut deps = mock_dependencies();
-        let env = mock_env();
         setup(deps.as_mut());
 
-        // Create an offering
         let sell_msg = SellRwa {
-            list_price: coin(10, "earth"),
+            list_price: coin(1000, "earth"),
+            listing_type: None,
+            rental_terms: None,
+            allowed_buyers: Some(vec!["buyer".into()]),
         };
         let msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
             sender: "owner".into(),
             amount: Uint128::new(100),
-            msg: to_json_binary(&sell_msg).unwrap(),
+            msg: to_json_binary(&ReceiveHookMsg::Sell(sell_msg)).unwrap(),
         });
-        let info = mock_info("rwa-token", &[]);
-        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("rwa-token", &[]), msg).unwrap();
+
+        for (viewer, listed) in [
+            (None, 0),
+            (Some("other"), 0),
+            (Some("buyer"), 1),
+            (Some("owner"), 1),
+        ] {
+            let msg = QueryMsg::AllOffers {
+                start_after: None,
+                limit: None,
+                viewer: viewer.map(Into::into),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let value: OffersResponse = from_json(&res).unwrap();
+            assert_eq!(listed, value.offers.len());
+        }
 
-        // Rent the RWA
         let msg = ExecuteMsg::RentRwa {
             offering_id: "1".into(),
-            duration: 30, // 30 seconds
+            duration: 1,
+            referrer: None,
+            max_total: None,
         };
-        let info = mock_info("renter", &coins(300, "earth")); // 10 * 30 = 300
-        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-
-        assert_eq!(2, res.messages.len());
-        assert_eq!(
-            res.messages[0],
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                to_address: "owner".into(),
-                amount: coins(294, "earth") // 300 - 2% fee
-            }))
-        );
-        assert_eq!(
-            res.messages[1],
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "rwa-token".into(),
-                msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-                    recipient: "renter".into(),
-                    amount: Uint128::new(100),
-                })
-                .unwrap(),
-                funds: vec![],
-            }))
-        );
-
-        // Check rental info
-        let msg = QueryMsg::GetRental {
-            rental_id: "1".into(),
+        let info = mock_info("other", &coins(1000, "earth"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::PrivateListing {} => {}
+            _ => panic!("Must return private listing error"),
+        }
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
         };
-        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
-        let rental_info: RentalResponse = from_json(&res).unwrap();
-        assert_eq!(rental_info.rental.renter, "renter");
-        assert_eq!(rental_info.rental.amount, Uint128::new(100));
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::PrivateListing {} => {}
+            _ => panic!("Must return private listing error"),
+        }
+
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     }
 
     #[test]
-    fn end_rental_and_clawback() {
+    fn bundle_listing() {
         let mut deps = mock_dependencies();
-        let mut env = mock_env();
         setup(deps.as_mut());
+        register_asset(deps.as_mut());
+        let royalty = |percent: u64| ExecuteMsg::SetRoyalty {
+            contract: "rwa-token".into(),
+            recipient: "issuer".into(),
+            rate: Decimal::percent(percent),
+        };
+        let issuer = mock_info("issuer", &[]);
+        execute(deps.as_mut(), mock_env(), issuer.clone(), royalty(5)).unwrap();
 
-        // List RWA
-        let list_price = coin(10, "earth");
-        let sell_msg = SellRwa {
-            list_price: list_price.clone(),
+        let msg = ExecuteMsg::CreateBundle {
+            assets: vec![
+                BundleItem {
+                    contract: "rwa-token".into(),
+                    amount: Uint128::new(100),
+                },
+                BundleItem {
+                    contract: "rwa-other".into(),
+                    amount: Uint128::new(50),
+                },
+            ],
+            list_price: coin(1000, "earth"),
+            allowed_buyers: None,
         };
-        let list_msg = ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
-            sender: "owner".into(),
-            amount: Uint128::new(100),
-            msg: to_json_binary(&sell_msg).unwrap(),
-        });
-        let list_info = mock_info("rwa-token", &[]);
-        let list_res = execute(deps.as_mut(), env.clone(), list_info, list_msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
 
-        // Check that the RWA was listed successfully
-        assert_eq!(0, list_res.messages.len());
-        assert_eq!(
-            list_res.attributes,
-            vec![
-                attr("action", "sell_rwa"),
-                attr("offering_id", "1"),
-                attr("rwa_contract", "rwa-token"),
-                attr("seller", "owner"),
-                attr(
-                    "list_price",
-                    format!("{}{}", list_price.amount, list_price.denom)
-                ),
-                attr("amount", "100"),
-            ]
-        );
+        let deposit = |amount: u128| {
+            ExecuteMsg::ReceiveRwa(Cw20ReceiveMsg {
+                sender: "owner".into(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(&ReceiveHookMsg::DepositBundle(DepositBundle {
+                    offering_id: "1".into(),
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info("rwa-other", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), deposit(40)).unwrap_err();
+        match err {
+            ContractError::InvalidBundle {} => {}
+            _ => panic!("Must return invalid bundle error"),
+        }
+        execute(deps.as_mut(), mock_env(), info, deposit(50)).unwrap();
 
-        // Rent the RWA
-        let rent_msg = ExecuteMsg::RentRwa {
+        // not listed until every asset is in
+        let msg = QueryMsg::GetPendingBundle {
             offering_id: "1".into(),
-            duration: 30, // 30 seconds
         };
-        let rent_info = mock_info("renter", &coins(300, "earth")); // 10 * 30 = 300
-        execute(deps.as_mut(), env.clone(), rent_info, rent_msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PendingBundleResponse = from_json(&res).unwrap();
+        assert_eq!(vec![false, true], value.deposited);
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let info = mock_info("buyer", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
 
-        // Fast forward time
-        env.block.time = env.block.time.plus_seconds(31);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("rwa-token", &[]),
+            deposit(100),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("listed", "true")));
 
-        // End rental
-        let end_msg = ExecuteMsg::EndRental {
-            rental_id: "1".into(),
+        // without a valuation for each asset the price is split evenly, so the
+        // royalty applies to half of it
+        let quote = QueryMsg::SimulateBuy {
+            offering_id: "1".into(),
+            amount: Uint128::new(1000),
+            buyer: None,
         };
-        let end_info = mock_info("renter", &[]);
-        let end_res = execute(deps.as_mut(), env.clone(), end_info, end_msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), quote.clone()).unwrap();
+        let value: QuoteResponse = from_json(&res).unwrap();
+        assert_eq!(coin(20, "earth"), value.protocol_fee);
+        assert_eq!(
+            vec![RoyaltyPayment {
+                recipient: Addr::unchecked("issuer"),
+                amount: coin(25, "earth"),
+            }],
+            value.royalties
+        );
+
+        // valued at 300 and 700, the royalty and a raised asset fee apply to 300
+        let msg = ExecuteMsg::RegisterAsset {
+            contract: "rwa-other".into(),
+            issuer: "issuer".into(),
+            income_denom: "earth".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        for (contract, price) in [("rwa-token", 3), ("rwa-other", 14)] {
+            let msg = ExecuteMsg::SetAssetValuation {
+                contract: contract.into(),
+                price: coin(price, "earth"),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAssetFees {
+            contract: "rwa-token".into(),
+            rates: Some(FeeRates {
+                buy: Decimal::percent(1),
+                rent: Decimal::percent(1),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), quote).unwrap();
+        let value: QuoteResponse = from_json(&res).unwrap();
+        assert_eq!(coin(3 + 14, "earth"), value.protocol_fee);
+        assert_eq!(coin(15, "earth"), value.royalty);
+        assert_eq!(coin(1000 - 17 - 15, "earth"), value.seller_amount);
 
-        assert_eq!(1, end_res.messages.len());
+        let msg = ExecuteMsg::Buy {
+            offering_id: "1".into(),
+            referrer: None,
+            max_price: None,
+            expected_fee: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(
-            end_res.messages[0],
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "rwa-token".into(),
+            res.messages[..2],
+            [
+                SubMsg::new(BankMsg::Send {
+                    to_address: "owner".into(),
+                    amount: coins(968, "earth"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "issuer".into(),
+                    amount: coins(15, "earth"),
+                }),
+            ]
+        );
+        let transfer = |contract: &str, amount: u128| {
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: contract.into(),
                 msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
-                    recipient: "owner".into(),
-                    amount: Uint128::new(100),
+                    recipient: "buyer".into(),
+                    amount: Uint128::new(amount),
                 })
                 .unwrap(),
                 funds: vec![],
-            }))
-        );
-
-        // Try clawback (should fail as rental is already ended)
-        let clawback_msg = ExecuteMsg::Clawback {
-            rental_id: "1".into(),
+            })
         };
-        let clawback_info = mock_info("owner", &[]);
-        let clawback_err =
-            execute(deps.as_mut(), env.clone(), clawback_info, clawback_msg).unwrap_err();
-        match clawback_err {
-            ContractError::RentalNotFound {} => {}
-            _ => panic!("Must return RentalNotFound error"),
-        }
+        assert_eq!(
+            res.messages[res.messages.len() - 2..],
+            [transfer("rwa-token", 100), transfer("rwa-other", 50)]
+        );
     }
 }
@@ -27,4 +27,151 @@ pub enum ContractError {
 
     #[error("Rental not expired")]
     RentalNotExpired {},
+
+    #[error("Asset not found")]
+    AssetNotFound {},
+
+    #[error("Asset already registered")]
+    AssetAlreadyRegistered {},
+
+    #[error("Asset has no registered holders")]
+    NoHolders {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Invalid royalty")]
+    InvalidRoyalty {},
+
+    #[error("Invalid fee split")]
+    InvalidFeeSplit {},
+
+    #[error("Invalid fee")]
+    InvalidFee {},
+
+    #[error("No ownership transfer pending")]
+    NoPendingOwner {},
+
+    #[error("Paused")]
+    Paused {},
+
+    #[error("Action must be proposed through governance")]
+    TimelockRequired {},
+
+    #[error("Proposal not found")]
+    ProposalNotFound {},
+
+    #[error("Proposal delay has not passed")]
+    ProposalNotReady {},
+
+    #[error("Renewals are disabled for this offering")]
+    RenewalsDisabled {},
+
+    #[error("Invalid penalty")]
+    InvalidPenalty {},
+
+    #[error("Offering is not for sale")]
+    NotForSale {},
+
+    #[error("Offering is not for rent")]
+    NotForRent {},
+
+    #[error("Invalid rental duration")]
+    InvalidDuration {},
+
+    #[error("Start time must be in the future")]
+    InvalidStartTime {},
+
+    #[error("Rental window overlaps an existing booking")]
+    WindowBooked {},
+
+    #[error("Offering has active or reserved rentals")]
+    OfferingBooked {},
+
+    #[error("Rental has not started")]
+    RentalNotStarted {},
+
+    #[error("Rental is already active")]
+    RentalAlreadyActive {},
+
+    #[error("Rented RWA has not been returned")]
+    RwaNotReturned {},
+
+    #[error("Invalid RWA return")]
+    InvalidReturn {},
+
+    #[error("Subletting is not allowed")]
+    SubletNotAllowed {},
+
+    #[error("Invalid sublet terms")]
+    InvalidSubletTerms {},
+
+    #[error("Invalid rent-to-own terms")]
+    InvalidRentToOwn {},
+
+    #[error("Rent-to-own purchase can't complete yet")]
+    PurchaseBlocked {},
+
+    #[error("Invalid lending terms")]
+    InvalidLendingTerms {},
+
+    #[error("Asset can't be borrowed against in this denom")]
+    LendingDisabled {},
+
+    #[error("Collateral has no valuation in the loan's denom")]
+    NoValuation {},
+
+    #[error("Borrow exceeds the loan-to-value limit")]
+    ExceedsLtv {},
+
+    #[error("Not enough liquidity in the pool")]
+    InsufficientLiquidity {},
+
+    #[error("Loan not found")]
+    LoanNotFound {},
+
+    #[error("Loan is not undercollateralized")]
+    LoanHealthy {},
+
+    #[error("Loan is being liquidated")]
+    LoanLiquidated {},
+
+    #[error("Invalid installment terms")]
+    InvalidInstallmentTerms {},
+
+    #[error("Offering can't be bought in installments")]
+    InstallmentsDisabled {},
+
+    #[error("Installment plan is not in default")]
+    InstallmentsCurrent {},
+
+    #[error("Invalid redemption window")]
+    InvalidRedemptionWindow {},
+
+    #[error("Redemption window is closed")]
+    RedemptionClosed {},
+
+    #[error("Redemption window is still open")]
+    RedemptionOpen {},
+
+    #[error("Invalid batch")]
+    InvalidBatch {},
+
+    #[error("Total price exceeds the maximum")]
+    MaxTotalExceeded {},
+
+    #[error("Price exceeds the maximum")]
+    MaxPriceExceeded {},
+
+    #[error("Fee exceeds the expected fee")]
+    FeeExceeded {},
+
+    #[error("Offering is reserved for other buyers")]
+    PrivateListing {},
+
+    #[error("Invalid bundle")]
+    InvalidBundle {},
+
+    #[error("Amount exceeds the collected fees")]
+    InsufficientFees {},
 }
@@ -1,4 +1,4 @@
 pub mod contract;
 pub mod error;
 pub mod msg;
-pub mod state;
\ No newline at end of file
+pub mod state;
@@ -1,45 +1,416 @@
-use cw20::Cw20ReceiveMsg;
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+use crate::state::{
+    BundleAsset, DenomFees, FeeRates, GovernanceAction, InstallmentTerms, LendingTerms,
+    ListingType, Operation, PendingChange, RentalTerms, Role, VolumeTier,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub fee: Decimal,
+    /// Upper bound for every fee rate, at most 0.9 so royalties still fit.
+    pub max_fee: Decimal,
+    /// Seconds of notice before a fee increase applies, at least a day.
+    pub fee_timelock: u64,
+    /// Seconds between proposing and executing a governed admin action.
+    pub governance_delay: u64,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Fails if the list price is above `max_price` or the protocol fee above
+    /// `expected_fee`.
     Buy {
         offering_id: String,
+        referrer: Option<String>,
+        max_price: Option<Uint128>,
+        expected_fee: Option<Uint128>,
+    },
+    /// Pays at least the down payment of the offering's installment terms.
+    /// The RWA is held by the contract until the rest is paid. Fails if the
+    /// price paid over the plan is above `max_total`.
+    BuyInstallments {
+        offering_id: String,
+        referrer: Option<String>,
+        max_total: Option<Uint128>,
+    },
+    /// only the buyer. Pays toward the plan with the attached funds. Not while
+    /// buying is paused, and the schedule is pushed back by the pause.
+    PayInstallment {
+        offering_id: String,
     },
+    /// only the seller, once the buyer has missed an installment. Returns the
+    /// RWA and keeps the forfeited share of the payments.
+    ReclaimInstallments {
+        offering_id: String,
+    },
+    /// only the seller. Reservations that haven't started are cancelled and
+    /// refunded in full.
     WithdrawRwa {
         offering_id: String,
     },
+    /// Buys all the offerings atomically, priced in the denom of `max_total`.
+    /// Fails if they cost more than `max_total`; any overpayment is refunded.
+    BatchBuy {
+        offering_ids: Vec<String>,
+        max_total: Coin,
+    },
+    /// only the seller of every offering.
+    BatchWithdraw {
+        offering_ids: Vec<String>,
+    },
+    /// Starts a sale-only offering of several assets at one price. It is listed
+    /// once each asset has been sent in with a `DepositBundle` hook. The price
+    /// is split across the assets by valuation to charge each one's royalty
+    /// and fees, evenly if any has no valuation in the price denom.
+    CreateBundle {
+        assets: Vec<BundleItem>,
+        list_price: Coin,
+        allowed_buyers: Option<Vec<String>>,
+    },
+    /// only the seller. Returns the assets deposited into a pending bundle.
+    CancelBundle {
+        offering_id: String,
+    },
     ReceiveRwa(Cw20ReceiveMsg),
-    /// only admin.
+    /// only admin or treasurer.
     WithdrawFees {
         amount: Uint128,
         denom: String,
     },
-    /// only admin.
+    /// only admin or fee manager. Sets both the default buy and rental rates.
+    /// Increases apply after the fee timelock.
     ChangeFee {
         fee: Decimal,
     },
+    /// Fails if the rent for `duration` plus the security deposit is above
+    /// `max_total`.
     RentRwa {
         offering_id: String,
         duration: u64,
+        referrer: Option<String>,
+        max_total: Option<Uint128>,
+    },
+    /// Rents for `duration` seconds, prepaying only as many seconds as the
+    /// attached funds cover. Rent accrues to the seller per second. Fails if
+    /// the rent prepaid plus the security deposit is above `max_total`.
+    StreamRent {
+        offering_id: String,
+        duration: u64,
+        referrer: Option<String>,
+        max_total: Option<Uint128>,
+    },
+    /// Books a future window up to a year ahead, escrowing the rent up front.
+    /// The protocol fee is only paid out once the rental activates. Fails if
+    /// the rent plus the security deposit is above `max_total`.
+    ReserveRental {
+        offering_id: String,
+        start_time: u64,
+        duration: u64,
+        max_total: Option<Uint128>,
     },
+    /// Anyone, once a reservation's start time has passed. Hands the RWA to the renter.
+    ActivateRental {
+        rental_id: String,
+    },
+    /// only the renter. Prepays more time on a streamed rental.
+    TopUpRent {
+        rental_id: String,
+    },
+    /// only the seller. Releases the rent accrued so far and the late fees the
+    /// renter's deposit covers.
+    ClaimRent {
+        rental_id: String,
+    },
+    /// only the renter, once the RWA is returned. Late fees owed have to be attached;
+    /// any excess is refunded.
     EndRental {
         rental_id: String,
     },
+    /// only the lessor, once the rental expires. Settles a returned rental; while
+    /// the renter keeps the RWA, takes what `ClaimRent` would and leaves it open.
     Clawback {
         rental_id: String,
     },
+    /// only the renter. Pays for the extra time at the offering's current rate.
+    ExtendRental {
+        rental_id: String,
+        additional_duration: u64,
+    },
+    /// only the seller.
+    SetRenewals {
+        offering_id: String,
+        enabled: bool,
+    },
+    /// only the seller. `lessor_cut` is the share of sublet rent paid on to the seller.
+    SetSubletPolicy {
+        offering_id: String,
+        allow_sublet: bool,
+        lessor_cut: Decimal,
+    },
+    /// only the seller. Puts `equity_rate` of every rent payment toward the list
+    /// price; the renter buys the lot once it is covered. Zero turns it off.
+    SetRentToOwn {
+        offering_id: String,
+        equity_rate: Decimal,
+    },
+    /// Anyone. Sells a rent-to-own offering to its renter once their equity
+    /// covers the list price, if that didn't happen with the covering payment.
+    CompletePurchase {
+        rental_id: String,
+    },
+    /// only the seller. Renters returning the RWA more than `grace_period` seconds
+    /// late pay `late_fee_rate` per overdue second, not counting time rental
+    /// settlement is paused. Renters put down `security_deposit` on top of the
+    /// rent, which covers late fees when the rental is clawed back or swept.
+    SetLateFees {
+        offering_id: String,
+        grace_period: u64,
+        late_fee_rate: Uint128,
+        security_deposit: Uint128,
+    },
+    /// only the seller. None turns installment purchases off.
+    SetInstallmentTerms {
+        offering_id: String,
+        terms: Option<InstallmentTerms>,
+    },
+    /// only the seller. Share of the unused rent kept when a renter ends early.
+    SetEarlyTerminationPenalty {
+        offering_id: String,
+        penalty: Decimal,
+    },
+    /// only admin or asset curator.
+    RegisterAsset {
+        contract: String,
+        issuer: String,
+        income_denom: String,
+    },
+    /// only the asset's issuer.
+    SetHolderShares {
+        contract: String,
+        holder: String,
+        shares: Uint128,
+    },
+    /// only the asset's issuer. Buys back up to `cap` tokens at `price` from
+    /// holders redeeming before `closes_at`; the attached funds must cover them all.
+    OpenRedemptionWindow {
+        contract: String,
+        price: Coin,
+        cap: Uint128,
+        closes_at: u64,
+    },
+    /// Anyone, once the window closed. Returns the unused funding to the issuer.
+    CloseRedemptionWindow {
+        window_id: u64,
+    },
+    /// Pays out a closed window's redemption and returns the unfilled tokens.
+    ClaimRedemption {
+        window_id: u64,
+    },
+    /// Distributes the attached `income_denom` funds pro-rata to the asset's holders.
+    DepositIncome {
+        contract: String,
+    },
+    ClaimIncome {
+        contract: String,
+    },
+    /// only the asset's issuer. A zero rate removes the royalty.
+    SetRoyalty {
+        contract: String,
+        recipient: String,
+        rate: Decimal,
+    },
+    /// only admin or asset curator. Price of one token of the asset.
+    SetAssetValuation {
+        contract: String,
+        price: Coin,
+    },
+    /// only admin or asset curator. `None` stops new borrowing against the asset;
+    /// open loans keep the terms they were opened with.
+    SetLendingTerms {
+        contract: String,
+        terms: Option<LendingTerms>,
+    },
+    /// Adds the attached funds to the lender pool of their denom.
+    DepositLiquidity {},
+    WithdrawLiquidity {
+        denom: String,
+        shares: Uint128,
+    },
+    /// only the borrower.
+    Borrow {
+        loan_id: u64,
+        amount: Uint128,
+    },
+    /// Repays with the attached funds; the collateral is returned once the debt is cleared.
+    /// A liquidated loan has to be repaid in full, which withdraws its forced listing.
+    Repay {
+        loan_id: u64,
+    },
+    /// Anyone. Lists the collateral of an undercollateralized loan for sale at
+    /// its valuation less the liquidation discount, or the debt if higher; the
+    /// proceeds repay the pool.
+    Liquidate {
+        loan_id: u64,
+    },
+    /// only admin or fee manager.
+    SetDefaultFees {
+        rates: FeeRates,
+    },
+    /// only admin or fee manager. `None` removes the override.
+    SetAssetFees {
+        contract: String,
+        rates: Option<FeeRates>,
+    },
+    /// only admin or fee manager.
+    SetDenomFees {
+        denom: String,
+        rates: Option<FeeRates>,
+        min_fee: Uint128,
+        tiers: Vec<VolumeTier>,
+    },
+    /// only admin or fee manager.
+    SetFeeRecipients {
+        recipients: Vec<FeeRecipientMsg>,
+        referrer_share: Decimal,
+    },
+    /// only admin or fee manager. `None` stops paying keepers.
+    SetKeeperBounty {
+        bounty: Option<Coin>,
+    },
+    /// Anyone. Looks at up to `limit` expired rentals after `start_after`,
+    /// settling those whose RWA is back, for the keeper bounty.
+    ProcessExpiredRentals {
+        start_after: Option<ExpiryKey>,
+        limit: Option<u32>,
+    },
+    /// only admin. The new owner has to accept.
+    ProposeOwner {
+        owner: String,
+    },
+    /// only the proposed owner.
+    AcceptOwnership {},
+    /// only admin. Leaves the contract without an owner for good.
+    RenounceOwnership {},
+    /// only admin.
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    /// only admin.
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// only admin or pauser. `None` pauses every operation.
+    Pause {
+        operations: Option<Vec<Operation>>,
+    },
+    /// only admin or pauser. `None` lifts every operation-level pause.
+    Unpause {
+        operations: Option<Vec<Operation>>,
+    },
+    /// only admin or pauser. Halts listing, buying, renting and settling of one asset.
+    PauseAsset {
+        contract: String,
+    },
+    /// only admin or pauser.
+    UnpauseAsset {
+        contract: String,
+    },
+    /// Queues a governed action; needs the same role as the direct message.
+    ProposeAction {
+        action: GovernanceAction,
+    },
+    /// only the proposer or admin, before the action is executed.
+    CancelAction {
+        id: u64,
+    },
+    /// Anyone, once the proposal's eta has passed.
+    ExecuteAction {
+        id: u64,
+    },
+}
+
+#[cw_serde]
+pub struct FeeRecipientMsg {
+    pub address: String,
+    pub share: Decimal,
+}
+
+/// The `msg` of a CW20 transfer into the contract, naming what the tokens are for.
+#[cw_serde]
+pub enum ReceiveHookMsg {
+    Sell(SellRwa),
+    /// Deposits one of a pending bundle's assets.
+    DepositBundle(DepositBundle),
+    /// Lists the tokens as several offerings whose amounts add up to the transfer.
+    SellBatch(SellBatch),
+    Sublet(SubletRwa),
+    Redeem(Redeem),
+    OpenLoan(OpenLoan),
+    /// Hands the rented RWA back so the rental can be settled.
+    ReturnRental(ReturnRental),
 }
 
 #[cw_serde]
 pub struct SellRwa {
     pub list_price: Coin,
+    /// Defaults to both sale and rent.
+    pub listing_type: Option<ListingType>,
+    /// Defaults to `list_price` per second with no duration bounds.
+    pub rental_terms: Option<RentalTerms>,
+    /// Restricts buying and renting to these addresses, hiding the offering
+    /// from everyone else's listings.
+    pub allowed_buyers: Option<Vec<String>>,
+}
+
+#[cw_serde]
+pub struct BundleItem {
+    pub contract: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct DepositBundle {
+    pub offering_id: String,
+}
+
+#[cw_serde]
+pub struct SellBatch {
+    pub lots: Vec<SellLot>,
+}
+
+#[cw_serde]
+pub struct SellLot {
+    pub amount: Uint128,
+    pub listing: SellRwa,
+}
+
+/// Sent with RWA tokens to redeem them in an open redemption window.
+#[cw_serde]
+pub struct Redeem {
+    pub window_id: u64,
+}
+
+/// Sent with RWA tokens of a registered asset to open a loan against them.
+#[cw_serde]
+pub struct OpenLoan {
+    pub denom: String,
+}
+
+#[cw_serde]
+pub struct ReturnRental {
+    pub rental_id: String,
+}
+
+/// Sent with the rented RWA to list the rest of the rental for sublet.
+#[cw_serde]
+pub struct SubletRwa {
+    pub rental_id: String,
+    pub rental_terms: RentalTerms,
 }
 
 #[cw_serde]
@@ -53,13 +424,98 @@ pub enum QueryMsg {
     /// Requires pagination. Lists all offers controlled by the contract.
     /// Return type: OffersResponse.
     #[returns(OffersResponse)]
+    /// Private offerings are only listed for their seller and allowed buyers
+    /// passed as `viewer`.
     AllOffers {
         start_after: Option<String>,
         limit: Option<u32>,
+        viewer: Option<String>,
     },
+    #[returns(PendingBundleResponse)]
+    GetPendingBundle { offering_id: String },
     #[returns(RentalResponse)]
-    GetRental {
-        rental_id: String,
+    GetRental { rental_id: String },
+    /// Booked windows of an offering, ordered by start time.
+    #[returns(BookingsResponse)]
+    Bookings {
+        offering_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Number of the next `limit` expired rentals after `start_after` that a
+    /// sweep can settle now.
+    #[returns(ExpiredRentalsResponse)]
+    ExpiredRentals {
+        start_after: Option<ExpiryKey>,
+        limit: Option<u32>,
+    },
+    #[returns(AssetResponse)]
+    GetAsset { contract: String },
+    #[returns(PoolResponse)]
+    GetPool { denom: String },
+    /// Value of a lender's pool shares.
+    #[returns(LenderResponse)]
+    LenderBalance { denom: String, lender: String },
+    #[returns(LoanResponse)]
+    GetLoan { loan_id: u64 },
+    #[returns(RedemptionWindowResponse)]
+    GetRedemptionWindow { window_id: u64 },
+    /// A holder's redemption, filled as things stand.
+    #[returns(RedemptionRequestResponse)]
+    RedemptionRequest { window_id: u64, holder: String },
+    #[returns(InstallmentPlanResponse)]
+    GetInstallmentPlan { offering_id: String },
+    #[returns(PendingIncomeResponse)]
+    PendingIncome { contract: String, holder: String },
+    /// Price breakdown for buying an offering at its list price, failing where
+    /// `buyer` couldn't buy it. With `amount` of the price denom attached, also
+    /// the refund.
+    #[returns(QuoteResponse)]
+    QuoteBuy {
+        offering_id: String,
+        buyer: Option<String>,
+        amount: Option<Uint128>,
+    },
+    /// Outcome of buying the offering with `amount` of its price denom attached;
+    /// `QuoteBuy` with the amount required.
+    #[returns(QuoteResponse)]
+    SimulateBuy {
+        offering_id: String,
+        amount: Uint128,
+        buyer: Option<String>,
+    },
+    /// Outcome of renting the offering for `duration` seconds from now, failing
+    /// where `renter` couldn't rent it.
+    #[returns(SimulateRentResponse)]
+    SimulateRent {
+        offering_id: String,
+        duration: u64,
+        renter: Option<String>,
+    },
+    #[returns(FeeScheduleResponse)]
+    GetFeeSchedule {},
+    #[returns(AssetFeesResponse)]
+    GetAssetFees { contract: String },
+    #[returns(DenomFeesResponse)]
+    GetDenomFees { denom: String },
+    #[returns(TraderVolumeResponse)]
+    TraderVolume { trader: String, denom: String },
+    /// Fees held by the contract and available to withdraw.
+    #[returns(CollectedFeesResponse)]
+    CollectedFees { denom: String },
+    #[returns(OwnershipResponse)]
+    Ownership {},
+    /// Roles held by `address`.
+    #[returns(RolesResponse)]
+    Roles { address: String },
+    #[returns(PauseStatusResponse)]
+    PauseStatus {},
+    #[returns(PendingActionResponse)]
+    PendingAction { id: u64 },
+    #[returns(PendingActionsResponse)]
+    PendingActions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
     },
 }
 
@@ -85,6 +541,29 @@ pub struct Offer {
     pub contract: Addr,
     pub seller: Addr,
     pub list_price: Coin,
+    pub listing_type: ListingType,
+    pub rental_terms: RentalTerms,
+    pub renewals_enabled: bool,
+    pub early_termination_penalty: Decimal,
+    pub allow_sublet: bool,
+    pub sublet_cut: Decimal,
+    pub parent_rental_id: Option<String>,
+    pub equity_rate: Decimal,
+    pub grace_period: u64,
+    pub late_fee_rate: Uint128,
+    pub security_deposit: Uint128,
+    pub loan_id: Option<u64>,
+    pub installment_terms: Option<InstallmentTerms>,
+    pub allowed_buyers: Vec<Addr>,
+    pub bundle: Vec<BundleAsset>,
+}
+
+#[cw_serde]
+pub struct PendingBundleResponse {
+    pub seller: Addr,
+    pub list_price: Coin,
+    pub assets: Vec<BundleAsset>,
+    pub deposited: Vec<bool>,
 }
 
 #[cw_serde]
@@ -95,9 +574,238 @@ pub struct RentalInfo {
     pub start_time: u64,
     pub end_time: u64,
     pub amount: Uint128,
+    pub escrow: Coin,
+    pub paid_until: u64,
+    pub claimed: Coin,
+    /// Accrued rent and deposit-covered late fees the seller can claim now.
+    pub claimable: Coin,
+    pub active: bool,
+    pub lessor: Addr,
+    pub parent_rental_id: Option<String>,
+    pub sublet_offering_id: Option<String>,
+    pub equity: Coin,
+    pub equity_rate: Decimal,
+    pub early_termination_penalty: Decimal,
+    pub returned_at: Option<u64>,
+    pub deposit: Coin,
+    /// Late fees owed on ending the rental now.
+    pub amount_owed: Coin,
 }
 
 #[cw_serde]
 pub struct RentalResponse {
     pub rental: RentalInfo,
 }
+
+/// A rental's place in the queue of rentals by end time.
+#[cw_serde]
+pub struct ExpiryKey {
+    pub end_time: u64,
+    pub rental_id: String,
+}
+
+#[cw_serde]
+pub struct ExpiredRentalsResponse {
+    pub count: u64,
+    /// Where to continue from; none once every expired rental was looked at.
+    pub last_scanned: Option<ExpiryKey>,
+}
+
+#[cw_serde]
+pub struct Booking {
+    pub rental_id: String,
+    pub renter: Addr,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+#[cw_serde]
+pub struct BookingsResponse {
+    pub bookings: Vec<Booking>,
+}
+
+#[cw_serde]
+pub struct AssetResponse {
+    pub contract: Addr,
+    pub issuer: Addr,
+    pub income_denom: String,
+    pub total_shares: Uint128,
+    pub income_per_share: Decimal,
+    pub royalty_recipient: Option<Addr>,
+    pub royalty_rate: Decimal,
+    pub valuation: Option<Coin>,
+    pub lending: Option<LendingTerms>,
+}
+
+#[cw_serde]
+pub struct PoolResponse {
+    pub liquidity: Coin,
+    pub borrowed: Coin,
+    pub total_shares: Uint128,
+}
+
+#[cw_serde]
+pub struct LenderResponse {
+    pub shares: Uint128,
+    pub value: Coin,
+}
+
+#[cw_serde]
+pub struct LoanResponse {
+    pub borrower: Addr,
+    pub contract: Addr,
+    pub collateral: Uint128,
+    /// Debt including interest accrued up to now.
+    pub debt: Coin,
+    pub collateral_value: Coin,
+    pub liquidatable: bool,
+    pub offering_id: Option<String>,
+    pub terms: LendingTerms,
+}
+
+#[cw_serde]
+pub struct RedemptionWindowResponse {
+    pub contract: Addr,
+    pub issuer: Addr,
+    pub price: Coin,
+    pub cap: Uint128,
+    pub requested: Uint128,
+    pub closes_at: u64,
+    pub closed: bool,
+}
+
+#[cw_serde]
+pub struct RedemptionRequestResponse {
+    pub tokens: Uint128,
+    pub filled: Uint128,
+    pub payout: Coin,
+}
+
+#[cw_serde]
+pub struct InstallmentPlanResponse {
+    pub buyer: Addr,
+    pub seller: Addr,
+    pub contract: Addr,
+    pub amount: Uint128,
+    pub price: Coin,
+    pub paid: Coin,
+    /// When the next installment falls due.
+    pub next_due: u64,
+    pub next_payment: Coin,
+    pub defaulted: bool,
+}
+
+#[cw_serde]
+pub struct PendingIncomeResponse {
+    pub shares: Uint128,
+    pub pending: Coin,
+}
+
+#[cw_serde]
+pub struct QuoteResponse {
+    pub price: Coin,
+    pub protocol_fee: Coin,
+    /// Sum of `royalties`.
+    pub royalty: Coin,
+    /// One per charged asset, as a bundle may hold several.
+    pub royalties: Vec<RoyaltyPayment>,
+    pub seller_amount: Coin,
+    /// Part of `amount` above the price, sent back to the buyer.
+    pub refund: Coin,
+}
+
+#[cw_serde]
+pub struct RoyaltyPayment {
+    pub recipient: Addr,
+    pub amount: Coin,
+}
+
+#[cw_serde]
+pub struct SimulateRentResponse {
+    /// Payment required for the rental.
+    pub price: Coin,
+    pub protocol_fee: Coin,
+    /// Set aside toward the list price on rent-to-own offerings.
+    pub equity: Coin,
+    /// Escrowed for the seller, released as the rental runs.
+    pub seller_amount: Coin,
+    /// Paid on top of the price against late fees, refunded on settlement.
+    pub deposit: Coin,
+}
+
+#[cw_serde]
+pub struct FeeScheduleResponse {
+    pub default: FeeRates,
+    pub pending_default: Option<PendingChange<FeeRates>>,
+    pub max_fee: Decimal,
+    pub fee_timelock: u64,
+    pub recipients: Vec<FeeRecipientInfo>,
+    pub referrer_share: Decimal,
+    pub keeper_bounty: Option<Coin>,
+}
+
+#[cw_serde]
+pub struct FeeRecipientInfo {
+    pub address: Addr,
+    pub share: Decimal,
+}
+
+#[cw_serde]
+pub struct AssetFeesResponse {
+    pub rates: Option<FeeRates>,
+    pub pending: Option<PendingChange<Option<FeeRates>>>,
+}
+
+#[cw_serde]
+pub struct DenomFeesResponse {
+    pub rates: Option<FeeRates>,
+    pub min_fee: Uint128,
+    pub tiers: Vec<VolumeTier>,
+    pub pending: Option<PendingChange<DenomFees>>,
+}
+
+#[cw_serde]
+pub struct TraderVolumeResponse {
+    pub volume: Coin,
+}
+
+#[cw_serde]
+pub struct CollectedFeesResponse {
+    pub collected: Coin,
+}
+
+#[cw_serde]
+pub struct OwnershipResponse {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct PauseStatusResponse {
+    pub all: bool,
+    pub operations: Vec<Operation>,
+    pub assets: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct ProposalInfo {
+    pub id: u64,
+    pub action: GovernanceAction,
+    pub proposer: Addr,
+    pub eta: u64,
+}
+
+#[cw_serde]
+pub struct PendingActionResponse {
+    pub action: ProposalInfo,
+}
+
+#[cw_serde]
+pub struct PendingActionsResponse {
+    pub actions: Vec<ProposalInfo>,
+}
@@ -1,14 +1,19 @@
 use cosmwasm_schema::cw_serde;
 
 use crate::error::ContractError;
-use cosmwasm_std::{Addr, Api, Coin, Decimal, StdResult, Storage, Uint128};
+use crate::msg::FeeRecipientMsg;
+use cosmwasm_std::{Addr, Api, Coin, Decimal, Empty, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
 pub struct State {
     pub num_offerings: u64,
-    pub fee: Decimal,
-    pub owner: Addr,
+    pub num_rentals: u64,
+    pub num_loans: u64,
+    pub num_redemptions: u64,
+    /// `None` once ownership has been renounced.
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
 }
 
 #[cw_serde]
@@ -17,9 +22,175 @@ pub struct Offering {
     pub contract: Addr,
     pub seller: Addr,
     pub list_price: Coin,
+    pub listing_type: ListingType,
+    pub rental_terms: RentalTerms,
+    /// Whether renters may extend their rentals.
+    pub renewals_enabled: bool,
+    /// Share of the unused rent kept by the seller when a renter ends early.
+    pub early_termination_penalty: Decimal,
+    /// Whether renters may sublet their remaining term.
+    pub allow_sublet: bool,
+    /// Share of sublet rent paid on to this offering's seller.
+    pub sublet_cut: Decimal,
+    /// Set on sublet offerings to the rental being sublet.
+    pub parent_rental_id: Option<String>,
+    /// Share of rent put toward buying the lot; zero unless rent-to-own.
+    pub equity_rate: Decimal,
+    /// Seconds after a rental's end before late fees start.
+    pub grace_period: u64,
+    /// Late fee per overdue second, in the rental denom.
+    pub late_fee_rate: Uint128,
+    /// Held from each renter against late fees, in the rental denom.
+    pub security_deposit: Uint128,
+    /// Set on forced listings of loan collateral; proceeds repay the loan.
+    pub loan_id: Option<u64>,
+    /// Lets buyers pay in installments; none for outright sales only.
+    pub installment_terms: Option<InstallmentTerms>,
+    /// Only these may buy or rent, and see the offering listed; anyone if empty.
+    pub allowed_buyers: Vec<Addr>,
+    /// Further tokens sold together with `amount` of `contract`.
+    pub bundle: Vec<BundleAsset>,
+}
+
+impl Offering {
+    pub fn allows(&self, addr: &Addr) -> bool {
+        self.allowed_buyers.is_empty() || self.allowed_buyers.contains(addr)
+    }
+
+    /// Whether `viewer` may see the offering in listings.
+    pub fn visible_to(&self, viewer: Option<&Addr>) -> bool {
+        match viewer {
+            Some(viewer) => viewer == self.seller || self.allows(viewer),
+            None => self.allowed_buyers.is_empty(),
+        }
+    }
 }
 
 pub const STATE: Item<State> = Item::new("state");
+
+#[cw_serde]
+pub struct BundleAsset {
+    pub contract: Addr,
+    pub amount: Uint128,
+}
+
+/// A bundle waiting for its seller to deposit every asset.
+#[cw_serde]
+pub struct PendingBundle {
+    pub seller: Addr,
+    pub list_price: Coin,
+    pub assets: Vec<BundleAsset>,
+    /// Whether each of `assets` has been deposited.
+    pub deposited: Vec<bool>,
+    pub allowed_buyers: Vec<Addr>,
+}
+
+/// Keyed by the offering id the bundle is listed under once complete.
+pub const PENDING_BUNDLES: Map<&str, PendingBundle> = Map::new("pending_bundles");
+
+#[cw_serde]
+pub enum ListingType {
+    Sale,
+    Rent,
+    Both,
+}
+
+impl ListingType {
+    pub fn for_sale(&self) -> bool {
+        matches!(self, ListingType::Sale | ListingType::Both)
+    }
+
+    pub fn for_rent(&self) -> bool {
+        matches!(self, ListingType::Rent | ListingType::Both)
+    }
+}
+
+#[cw_serde]
+pub enum RentPeriod {
+    Second,
+    Day,
+    /// 30 days.
+    Month,
+}
+
+impl RentPeriod {
+    pub fn seconds(&self) -> u64 {
+        match self {
+            RentPeriod::Second => 1,
+            RentPeriod::Day => 86_400,
+            RentPeriod::Month => 2_592_000,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct RentalTerms {
+    /// Rent charged per `period`.
+    pub rate: Coin,
+    pub period: RentPeriod,
+    /// Bounds on a rental's duration in seconds.
+    pub min_duration: u64,
+    pub max_duration: Option<u64>,
+}
+
+impl RentalTerms {
+    /// Rent for `seconds`, rounded up so a short rental at a daily or monthly
+    /// rate isn't free.
+    pub fn price(&self, seconds: u64) -> StdResult<Uint128> {
+        let period = Uint128::from(self.period.seconds());
+        let total = self.rate.amount.checked_mul(Uint128::from(seconds))?;
+        Ok(total.checked_add(period - Uint128::one())? / period)
+    }
+
+    /// Seconds of rent that `amount` pays for, capped at `max`.
+    pub fn seconds_covered(&self, amount: Uint128, max: u64) -> u64 {
+        match amount.checked_multiply_ratio(self.period.seconds(), self.rate.amount) {
+            Ok(seconds) => seconds.min(Uint128::from(max)).u128() as u64,
+            Err(_) => max,
+        }
+    }
+
+    pub fn validate_duration(&self, duration: u64) -> Result<(), ContractError> {
+        if duration < self.min_duration || self.max_duration.is_some_and(|max| duration > max) {
+            return Err(ContractError::InvalidDuration {});
+        }
+        Ok(())
+    }
+}
+
+/// Admin duties that the owner can delegate. The owner implicitly holds every role.
+#[cw_serde]
+pub enum Role {
+    FeeManager,
+    ComplianceOfficer,
+    AssetCurator,
+    Pauser,
+    Treasurer,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::FeeManager => "fee_manager",
+            Role::ComplianceOfficer => "compliance_officer",
+            Role::AssetCurator => "asset_curator",
+            Role::Pauser => "pauser",
+            Role::Treasurer => "treasurer",
+        }
+    }
+
+    pub fn all() -> [Role; 5] {
+        [
+            Role::FeeManager,
+            Role::ComplianceOfficer,
+            Role::AssetCurator,
+            Role::Pauser,
+            Role::Treasurer,
+        ]
+    }
+}
+
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
 pub const OFFERINGS: Map<&str, Offering> = Map::new("offerings");
 
 pub fn increment_offerings(store: &mut dyn Storage) -> Result<u64, ContractError> {
@@ -33,6 +204,39 @@ pub fn increment_offerings(store: &mut dyn Storage) -> Result<u64, ContractError
     Ok(num)
 }
 
+pub fn increment_rentals(store: &mut dyn Storage) -> Result<u64, ContractError> {
+    let mut num = 0;
+    STATE.update(store, |mut state| -> Result<_, ContractError> {
+        state.num_rentals += 1;
+        num = state.num_rentals;
+        Ok(state)
+    })?;
+
+    Ok(num)
+}
+
+pub fn increment_loans(store: &mut dyn Storage) -> Result<u64, ContractError> {
+    let mut num = 0;
+    STATE.update(store, |mut state| -> Result<_, ContractError> {
+        state.num_loans += 1;
+        num = state.num_loans;
+        Ok(state)
+    })?;
+
+    Ok(num)
+}
+
+pub fn increment_redemptions(store: &mut dyn Storage) -> Result<u64, ContractError> {
+    let mut num = 0;
+    STATE.update(store, |mut state| -> Result<_, ContractError> {
+        state.num_redemptions += 1;
+        num = state.num_redemptions;
+        Ok(state)
+    })?;
+
+    Ok(num)
+}
+
 pub fn get_fund(funds: Vec<Coin>, denom: String) -> Result<Coin, ContractError> {
     for fund in funds.into_iter() {
         if fund.denom == denom {
@@ -55,6 +259,446 @@ pub struct Rental {
     pub start_time: u64,
     pub end_time: u64,
     pub amount: Uint128,
+    /// Seller's share of the rent, accruing per second up to `paid_until`.
+    pub escrow: Uint128,
+    /// End of the prepaid time; before `end_time` for streamed rentals.
+    pub paid_until: u64,
+    /// Escrow and deposit-covered late fees already paid to the seller.
+    pub claimed: Uint128,
+    /// Whether the RWA has been handed to the renter; reservations start inactive.
+    pub active: bool,
+    /// Party the RWA returns to, who may claw it back.
+    pub lessor: Addr,
+    /// The rental this one sublets, if any.
+    pub parent_rental_id: Option<String>,
+    /// Offering listing this rental's remaining term for sublet.
+    pub sublet_offering_id: Option<String>,
+    /// Rent set aside toward the list price on rent-to-own offerings.
+    pub equity: Uint128,
+    /// The offering's equity rate when the rental was booked.
+    pub equity_rate: Decimal,
+    /// The offering's early termination penalty when the rental was booked.
+    pub early_termination_penalty: Decimal,
+    /// The offering's late fee terms when the rental was booked.
+    pub grace_period: u64,
+    pub late_fee_rate: Uint128,
+    /// Held against late fees and refunded on settlement.
+    pub deposit: Uint128,
+    /// When the renter handed the RWA back to the contract.
+    pub returned_at: Option<u64>,
+    /// Protocol fee taken out of this rental's rent.
+    pub fee_paid: Uint128,
+    /// Part of `fee_paid` held until a reservation activates, so cancelling it
+    /// refunds everything.
+    pub fee_held: Uint128,
+}
+
+impl Rental {
+    /// Escrow accrued to the seller by `now`.
+    pub fn accrued(&self, now: u64) -> Uint128 {
+        if self.paid_until <= self.start_time {
+            return Uint128::zero();
+        }
+        let elapsed = now.clamp(self.start_time, self.paid_until) - self.start_time;
+        self.escrow
+            .multiply_ratio(elapsed, self.paid_until - self.start_time)
+    }
 }
 
 pub const RENTALS: Map<&str, Rental> = Map::new("rentals");
+
+#[cw_serde]
+pub struct InstallmentTerms {
+    /// Share of the list price paid up front.
+    pub down_payment: Decimal,
+    /// Number of equal installments covering the rest.
+    pub installments: u32,
+    /// Seconds between installments.
+    pub interval: u64,
+    /// Share of the paid amount the seller keeps if the buyer defaults.
+    pub forfeit_rate: Decimal,
+}
+
+impl InstallmentTerms {
+    pub fn down_payment(&self, price: Uint128) -> Uint128 {
+        price * self.down_payment
+    }
+
+    /// Size of each installment, rounded up.
+    pub fn installment(&self, price: Uint128) -> Uint128 {
+        let rest = price - self.down_payment(price);
+        let n = Uint128::from(self.installments);
+        (rest + n - Uint128::one()) / n
+    }
+
+    /// Total that has to be paid `elapsed` seconds into the plan.
+    pub fn due(&self, price: Uint128, elapsed: u64) -> Uint128 {
+        let passed = (elapsed / self.interval).min(self.installments.into());
+        let due = self.down_payment(price) + self.installment(price) * Uint128::from(passed);
+        due.min(price)
+    }
+}
+
+#[cw_serde]
+pub struct InstallmentPlan {
+    /// The offering bought, removed from the listings while the plan runs.
+    pub offering: Offering,
+    pub terms: InstallmentTerms,
+    pub buyer: Addr,
+    pub referrer: Option<Addr>,
+    pub start_time: u64,
+    /// Held by the contract until the plan completes or defaults.
+    pub paid: Uint128,
+}
+
+impl InstallmentPlan {
+    /// Whether payments are behind schedule, not counting the `paused` seconds
+    /// since the plan started during which they couldn't be made.
+    pub fn defaulted(&self, now: u64, paused: u64) -> bool {
+        let price = self.offering.list_price.amount;
+        let elapsed = now.saturating_sub(self.start_time).saturating_sub(paused);
+        self.paid < self.terms.due(price, elapsed)
+    }
+}
+
+/// Running installment purchases, keyed by offering id.
+pub const INSTALLMENT_PLANS: Map<&str, InstallmentPlan> = Map::new("installment_plans");
+
+/// Booked windows per offering, keyed by start time, pointing at the rental.
+pub const BOOKINGS: Map<(&str, u64), String> = Map::new("bookings");
+
+/// Rentals by end time, for sweeping expired ones.
+pub const EXPIRIES: Map<(u64, &str), Empty> = Map::new("expiries");
+
+#[cw_serde]
+pub struct Asset {
+    pub issuer: Addr,
+    pub income_denom: String,
+    pub total_shares: Uint128,
+    /// Cumulative income paid per registered share.
+    pub income_per_share: Decimal,
+    pub royalty: Option<Royalty>,
+    /// Price of one token, used to value loan collateral.
+    pub valuation: Option<Coin>,
+    pub lending: Option<LendingTerms>,
+}
+
+#[cw_serde]
+pub struct LendingTerms {
+    /// Largest debt to collateral value ratio a borrower may draw to.
+    pub max_ltv: Decimal,
+    /// Ratio above which the loan can be liquidated.
+    pub liquidation_ltv: Decimal,
+    /// Yearly interest rate.
+    pub interest_rate: Decimal,
+    /// Taken off the collateral value when it is listed for liquidation.
+    pub liquidation_discount: Decimal,
+}
+
+/// Native liquidity lent out against RWA collateral, one pool per denom.
+#[cw_serde]
+#[derive(Default)]
+pub struct Pool {
+    /// Funds available to borrow or withdraw.
+    pub liquidity: Uint128,
+    /// Outstanding debt, including accrued interest.
+    pub borrowed: Uint128,
+    pub total_shares: Uint128,
+}
+
+impl Pool {
+    /// Value of the pool that lender shares are a claim on.
+    pub fn value(&self) -> Uint128 {
+        self.liquidity + self.borrowed
+    }
+}
+
+#[cw_serde]
+pub struct Loan {
+    pub borrower: Addr,
+    pub contract: Addr,
+    pub collateral: Uint128,
+    pub denom: String,
+    /// Debt as of `accrued_at`.
+    pub debt: Uint128,
+    pub accrued_at: u64,
+    /// Forced listing of the collateral once liquidated.
+    pub offering_id: Option<String>,
+    /// The asset's lending terms when the loan was opened.
+    pub terms: LendingTerms,
+}
+
+pub const POOLS: Map<&str, Pool> = Map::new("pools");
+pub const POOL_SHARES: Map<(&str, &Addr), Uint128> = Map::new("pool_shares");
+pub const LOANS: Map<u64, Loan> = Map::new("loans");
+
+#[cw_serde]
+pub struct RedemptionWindow {
+    pub contract: Addr,
+    pub issuer: Addr,
+    /// Paid per redeemed token.
+    pub price: Coin,
+    /// Most tokens the issuer buys back.
+    pub cap: Uint128,
+    /// Deposited by the issuer to pay for `cap` tokens.
+    pub funding: Uint128,
+    pub closes_at: u64,
+    /// Tokens sent in for redemption, possibly above the cap.
+    pub requested: Uint128,
+    /// Requested tokens whose holders have claimed.
+    pub claimed: Uint128,
+    /// Funding paid out to holders or back to the issuer.
+    pub released: Uint128,
+    /// Whether the unused funding went back to the issuer.
+    pub closed: bool,
+}
+
+impl RedemptionWindow {
+    /// Part of `tokens` redeemed, scaled down pro-rata when oversubscribed.
+    pub fn fill(&self, tokens: Uint128) -> Uint128 {
+        if self.requested <= self.cap {
+            return tokens;
+        }
+        tokens.multiply_ratio(self.cap, self.requested)
+    }
+}
+
+pub const REDEMPTIONS: Map<u64, RedemptionWindow> = Map::new("redemptions");
+/// Tokens each holder sent in per window.
+pub const REDEMPTION_REQUESTS: Map<(u64, &Addr), Uint128> = Map::new("redemption_requests");
+
+#[cw_serde]
+pub struct Royalty {
+    pub recipient: Addr,
+    pub rate: Decimal,
+}
+
+#[cw_serde]
+pub struct Holder {
+    pub shares: Uint128,
+    /// Snapshot of `Asset.income_per_share` at the last settlement.
+    pub income_per_share: Decimal,
+    pub pending: Uint128,
+}
+
+pub const ASSETS: Map<&Addr, Asset> = Map::new("assets");
+pub const HOLDERS: Map<(&Addr, &Addr), Holder> = Map::new("holders");
+
+#[cw_serde]
+pub struct FeeRates {
+    pub buy: Decimal,
+    pub rent: Decimal,
+}
+
+#[cw_serde]
+pub struct VolumeTier {
+    /// Cumulative traded volume, in the tier's denom, needed to qualify.
+    pub min_volume: Uint128,
+    /// Fraction taken off the applicable rate.
+    pub discount: Decimal,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct DenomFees {
+    pub rates: Option<FeeRates>,
+    /// Smallest fee charged, capped at the maximum rate of the amount.
+    pub min_fee: Uint128,
+    pub tiers: Vec<VolumeTier>,
+}
+
+#[cw_serde]
+pub struct FeeRecipient {
+    pub address: Addr,
+    pub share: Decimal,
+}
+
+#[cw_serde]
+pub struct PendingChange<T> {
+    pub value: T,
+    pub effective_at: u64,
+}
+
+/// A fee setting together with a change scheduled for later.
+#[cw_serde]
+pub struct Timelocked<T> {
+    pub current: T,
+    pub pending: Option<PendingChange<T>>,
+}
+
+impl<T: Clone> Timelocked<T> {
+    pub fn new(value: T) -> Self {
+        Timelocked {
+            current: value,
+            pending: None,
+        }
+    }
+
+    /// The value in force at `now`.
+    pub fn active(&self, now: u64) -> &T {
+        match &self.pending {
+            Some(pending) if pending.effective_at <= now => &pending.value,
+            _ => &self.current,
+        }
+    }
+
+    /// Replaces any pending change with `value`, applied immediately or at `effective_at`.
+    pub fn set(&mut self, value: T, now: u64, effective_at: Option<u64>) {
+        self.current = self.active(now).clone();
+        self.pending = None;
+        match effective_at {
+            Some(effective_at) => {
+                self.pending = Some(PendingChange {
+                    value,
+                    effective_at,
+                })
+            }
+            None => self.current = value,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct FeeSchedule {
+    pub default: Timelocked<FeeRates>,
+    /// Upper bound for every buy and rental rate.
+    pub max_fee: Decimal,
+    /// Seconds before a fee increase takes effect.
+    pub fee_timelock: u64,
+    /// Shares of every collected fee paid out directly. Whatever is left
+    /// stays in the contract for `WithdrawFees`.
+    pub recipients: Vec<FeeRecipient>,
+    /// Share of the fee paid to the referrer named in `Buy` or `RentRwa`.
+    pub referrer_share: Decimal,
+    /// Paid from collected fees for every rental settled by `ProcessExpiredRentals`.
+    pub keeper_bounty: Option<Coin>,
+}
+
+pub const FEE_SCHEDULE: Item<FeeSchedule> = Item::new("fee_schedule");
+pub const ASSET_FEES: Map<&Addr, Timelocked<Option<FeeRates>>> = Map::new("asset_fees");
+pub const DENOM_FEES: Map<&str, Timelocked<DenomFees>> = Map::new("denom_fees");
+pub const TRADER_VOLUME: Map<(&Addr, &str), Uint128> = Map::new("trader_volume");
+/// Protocol fees kept in the contract per denom, the only funds `WithdrawFees` can take.
+pub const COLLECTED_FEES: Map<&str, Uint128> = Map::new("collected_fees");
+
+/// Operations that can be halted individually. Withdrawing a listing is never paused.
+#[cw_serde]
+pub enum Operation {
+    Listing,
+    Buy,
+    Rent,
+    RentalSettlement,
+    Lending,
+    Redemption,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseState {
+    /// Halts every pausable operation.
+    pub all: bool,
+    pub operations: Vec<Operation>,
+}
+
+impl PauseState {
+    pub fn halts_settlement(&self) -> bool {
+        self.all || self.operations.contains(&Operation::RentalSettlement)
+    }
+
+    pub fn halts_buy(&self) -> bool {
+        self.all || self.operations.contains(&Operation::Buy)
+    }
+}
+
+impl Operation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Listing => "listing",
+            Operation::Buy => "buy",
+            Operation::Rent => "rent",
+            Operation::RentalSettlement => "rental_settlement",
+            Operation::Lending => "lending",
+            Operation::Redemption => "redemption",
+        }
+    }
+}
+
+pub const PAUSE: Item<PauseState> = Item::new("pause");
+pub const PAUSED_ASSETS: Map<&Addr, Empty> = Map::new("paused_assets");
+/// Spans rental settlement was paused, by scope ("" contract-wide, else the asset
+/// contract) and start, to their end; `u64::MAX` while still paused.
+pub const SETTLEMENT_PAUSES: Map<(&str, u64), u64> = Map::new("settlement_pauses");
+/// Spans buying was paused, keyed like `SETTLEMENT_PAUSES`; installment
+/// schedules stand still through them.
+pub const BUY_PAUSES: Map<(&str, u64), u64> = Map::new("buy_pauses");
+
+/// Admin actions that go through the governance timelock.
+#[cw_serde]
+pub enum GovernanceAction {
+    ChangeFee {
+        fee: Decimal,
+    },
+    SetDefaultFees {
+        rates: FeeRates,
+    },
+    SetAssetFees {
+        contract: String,
+        rates: Option<FeeRates>,
+    },
+    SetDenomFees {
+        denom: String,
+        rates: Option<FeeRates>,
+        min_fee: Uint128,
+        tiers: Vec<VolumeTier>,
+    },
+    SetFeeRecipients {
+        recipients: Vec<FeeRecipientMsg>,
+        referrer_share: Decimal,
+    },
+    SetKeeperBounty {
+        bounty: Option<Coin>,
+    },
+    SetAssetValuation {
+        contract: String,
+        price: Coin,
+    },
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// Halts all trading in the asset; sellers can still withdraw.
+    DelistAsset {
+        contract: String,
+    },
+    Unpause {
+        operations: Option<Vec<Operation>>,
+    },
+    UnpauseAsset {
+        contract: String,
+    },
+    SetGovernanceDelay {
+        delay: u64,
+    },
+}
+
+#[cw_serde]
+pub struct GovernanceConfig {
+    /// Seconds between proposing and executing an action. Zero lets admins act directly.
+    pub delay: u64,
+    pub num_proposals: u64,
+}
+
+#[cw_serde]
+pub struct Proposal {
+    pub action: GovernanceAction,
+    pub proposer: Addr,
+    /// Earliest time the action can be executed.
+    pub eta: u64,
+}
+
+pub const GOVERNANCE: Item<GovernanceConfig> = Item::new("governance");
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");